@@ -0,0 +1,241 @@
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+
+use crate::{
+    client::{AnimeSummary, Client, Episode, LatestEpisode, ResolvedEpisode, ScheduledEpisode},
+    config::CONFIG,
+    provider::{Provider, PROVIDERS},
+    worker::Progress,
+};
+
+/// A series found in one or more providers
+pub struct AggregatedAnime {
+    pub title: String,
+    pub entries: Vec<AnimeSummary>,
+}
+
+/// Searches every registered provider concurrently and merges the results by title. Providers
+/// that fail are reported in the second value unless all of them fail.
+pub fn search_all(query: &str) -> Result<(Vec<AggregatedAnime>, Vec<String>)> {
+    let results = query_all(|client| client.get_animes(query, 1));
+
+    let mut aggregated: Vec<AggregatedAnime> = Vec::new();
+    let mut errors = Vec::new();
+    for (provider, result) in results {
+        let series = match result {
+            Ok(series) => series.animes,
+            Err(e) => {
+                errors.push(format!("{}: {}", provider.name, e));
+                continue;
+            }
+        };
+
+        for summary in series {
+            let key = normalize_title(&summary.title);
+            match aggregated
+                .iter_mut()
+                .find(|anime| normalize_title(&anime.title) == key)
+            {
+                Some(anime) => anime.entries.push(summary),
+                None => aggregated.push(AggregatedAnime {
+                    title: summary.title.clone(),
+                    entries: vec![summary],
+                }),
+            }
+        }
+    }
+
+    if aggregated.is_empty() && errors.len() == PROVIDERS.all().len() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+
+    Ok((aggregated, errors))
+}
+
+/// Latest episodes of every provider, interleaved so the newest of each provider come first
+pub fn latest_all() -> Result<(Vec<LatestEpisode>, Vec<String>)> {
+    let results = query_all(|client| client.latest());
+
+    let mut lists = Vec::new();
+    let mut errors = Vec::new();
+    for (provider, result) in results {
+        match result {
+            Ok(latest) => lists.push(latest.into_iter()),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    if lists.is_empty() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+
+    let mut latest = Vec::new();
+    loop {
+        let count = latest.len();
+        for list in &mut lists {
+            latest.extend(list.next());
+        }
+        if latest.len() == count {
+            break;
+        }
+    }
+
+    Ok((latest, errors))
+}
+
+/// Next episodes of the airing series of every provider from today on, by date and with the
+/// liked series first within each day, then by air time
+pub fn schedule_all() -> Result<(Vec<ScheduledEpisode>, Vec<String>)> {
+    let results = query_all(|client| client.schedule());
+
+    let mut schedule: Vec<ScheduledEpisode> = Vec::new();
+    let mut errors = Vec::new();
+    for (provider, result) in results {
+        match result {
+            Ok(episodes) => schedule.extend(episodes),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+    if schedule.is_empty() && errors.len() == PROVIDERS.all().len() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+
+    let today = Local::now().date_naive();
+    schedule.retain(|scheduled| scheduled.date >= today);
+
+    let config = CONFIG.read().unwrap();
+    let liked = config.get_liked_animes();
+    schedule.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then(
+                liked
+                    .contains(&b.series.title)
+                    .cmp(&liked.contains(&a.series.title)),
+            )
+            .then(a.time.cmp(&b.time))
+            .then(a.series.title.cmp(&b.series.title))
+    });
+    // Series offered by several providers appear once
+    let mut seen = Vec::new();
+    schedule.retain(|scheduled| {
+        let key = normalize_title(&scheduled.series.title);
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+
+    Ok((schedule, errors))
+}
+
+/// Looks for the same series and episode in the other providers, for when the provider of the
+/// series can not resolve the link. Only providers with the episode in the language are used.
+/// Returns the provider that served the episode and the stream.
+pub fn fallback_episode_link(
+    series: &AnimeSummary,
+    episode: &Episode,
+    language: &str,
+    progress: &Progress,
+) -> Result<(String, ResolvedEpisode)> {
+    let key = normalize_title(&series.title);
+    let mut errors = Vec::new();
+
+    for provider in PROVIDERS
+        .all()
+        .iter()
+        .filter(|provider| provider.id != series.provider)
+    {
+        progress.report(&format!("Buscando el episodio en {}", provider.name));
+        let result = provider
+            .client
+            .get_animes(&series.title, 1)
+            .and_then(|results| {
+                results
+                    .animes
+                    .into_iter()
+                    .find(|result| normalize_title(&result.title) == key)
+                    .ok_or(anyhow!("No tiene la serie"))
+            })
+            .and_then(|found| {
+                let same_episode = provider
+                    .client
+                    .episodes(&found.slug)?
+                    .into_iter()
+                    .find(|other| same_number(other, episode))
+                    .ok_or(anyhow!("No tiene el episodio {}", episode.number))?;
+                // Groups outside the list of the provider are only known by the episodes
+                if !provider.languages.iter().any(|other| other == language)
+                    && !same_episode.languages.iter().any(|other| other == language)
+                {
+                    return Err(anyhow!("No tiene el episodio en {}", language));
+                }
+                provider.client.get_episode_link(
+                    &found.slug,
+                    &same_episode,
+                    language,
+                    &provider.headers,
+                )
+            });
+
+        match result {
+            Ok(resolved) => return Ok((provider.id.clone(), resolved)),
+            Err(e) => errors.push(format!("{}: {}", provider.name, e)),
+        }
+    }
+
+    Err(anyhow!(errors.join("\n")))
+}
+
+/// Runs the call on every provider at the same time, in the order of the registry
+fn query_all<T, F>(call: F) -> Vec<(&'static Provider, Result<T>)>
+where
+    T: Send,
+    F: Fn(&dyn Client) -> Result<T> + Sync,
+{
+    thread::scope(|scope| {
+        let handles = PROVIDERS
+            .all()
+            .iter()
+            .map(|provider| (provider, scope.spawn(|| call(provider.client.as_ref()))))
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|(provider, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("El proveedor ha fallado")));
+                (provider, result)
+            })
+            .collect()
+    })
+}
+
+/// Numbers are compared as text too as specials are not numeric, e.g. "Final"
+fn same_number(a: &Episode, b: &Episode) -> bool {
+    match (a.number.parse::<f64>(), b.number.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.number.eq_ignore_ascii_case(&b.number),
+    }
+}
+
+/// Lowercase title without accents, punctuation or repeated spaces
+pub fn normalize_title(title: &str) -> String {
+    let title = title
+        .to_lowercase()
+        .chars()
+        .map(|char| match char {
+            'á' | 'à' | 'ä' | 'â' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ñ' => 'n',
+            char if char.is_alphanumeric() => char,
+            _ => ' ',
+        })
+        .collect::<String>();
+
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -1,23 +1,241 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Ok, Result};
-use reqwest::blocking;
 use scraper::{Html, Selector};
+use serde::Deserialize;
+
+use crate::{
+    client::{
+        AnimeKind, AnimeStatus, AnimeSummary, Client, Episode, Filters, LatestEpisode, SearchPage,
+        Season, SeriesDetails, Source, SourceKind,
+    },
+    mirrors::{self, Mirrors},
+    page_data,
+};
 
-use crate::{client::Client, config::CONFIG, frontend::Frontend};
+pub const ID: &str = "AnimeAv1";
+pub const BASE_URL: &str = "https://animeav1.com";
+/// Embed groups most series have, the episodes tell the others (e.g. "LAT")
+pub const LANGUAGES: [&str; 2] = ["SUB", "DUB"];
+/// Embed groups the episode cards can be tagged with, the other tags are not languages (e.g.
+/// "OVA" or "HD")
+const LANGUAGE_TAGS: [&str; 4] = ["SUB", "DUB", "LAT", "CAST"];
 
-#[derive(Default)]
 pub struct AnimeAv1 {
-    series_links: Vec<String>,
-    name: String,
+    mirrors: Mirrors,
+}
+
+/// Entry of the `embeds` and `downloads` groups of the episode pages
+#[derive(Deserialize)]
+struct Embed {
+    server: String,
+    url: String,
 }
 
 impl Client for AnimeAv1 {
-    fn get_animes(&mut self, query: &str) -> Result<Vec<String>> {
-        let url = format!("https://animeav1.com/catalogo?search={}", query);
-        let response = blocking::get(url)?;
+    fn get_animes(&self, query: &str, page: u32) -> Result<SearchPage> {
+        let path = format!("/catalogo?search={}&page={}", mirrors::encode(query), page);
+        self.catalog_page(&path, page)
+    }
+
+    fn browse(&self, filters: &Filters, page: u32) -> Result<SearchPage> {
+        let mut path = format!("/catalogo?page={}", page);
+        if let Some(genre) = &filters.genre {
+            path.push_str(&format!("&genre={}", genre));
+        }
+        if let Some(year) = filters.year {
+            path.push_str(&format!("&minYear={}&maxYear={}", year, year));
+        }
+        if let Some(season) = filters.season {
+            let season = match season {
+                Season::Winter => "invierno",
+                Season::Spring => "primavera",
+                Season::Summer => "verano",
+                Season::Fall => "otono",
+            };
+            path.push_str(&format!("&season={}", season));
+        }
+        if let Some(kind) = filters.kind {
+            let kind = match kind {
+                AnimeKind::Tv => "tv-anime",
+                AnimeKind::Movie => "pelicula",
+                AnimeKind::Ova => "ova",
+                AnimeKind::Ona => "ona",
+                AnimeKind::Special => "especial",
+            };
+            path.push_str(&format!("&category={}", kind));
+        }
+        if let Some(status) = filters.status {
+            let status = match status {
+                AnimeStatus::Airing => "emision",
+                AnimeStatus::Finished => "finalizado",
+                AnimeStatus::Upcoming => "proximamente",
+            };
+            path.push_str(&format!("&status={}", status));
+        }
+
+        self.catalog_page(&path, page)
+    }
+
+    fn latest(&self) -> Result<Vec<LatestEpisode>> {
+        let response = self.mirrors.get("/")?;
+        let html = Html::parse_document(&response.text()?);
+
+        let mut latest = Vec::new();
+        for (position, article) in html
+            .select(&Selector::parse("article").expect("Invalid selector"))
+            .enumerate()
+        {
+            // Episode cards link to /media/one-piece/1100, series cards only to /media/one-piece
+            let Some((series_id, number)) = article
+                .select(&Selector::parse("a").expect("Invalid selector"))
+                .filter_map(|link| link.attr("href"))
+                .find(|href| href.starts_with("/media/") && href.matches('/').count() == 3)
+                .and_then(|href| href.rsplit_once('/'))
+            else {
+                continue;
+            };
+
+            let Some(tittle) = article
+                .select(&Selector::parse("h3, header, img").expect("Invalid selector"))
+                .find_map(|element| element.text().next().or(element.attr("alt")))
+            else {
+                continue;
+            };
+
+            let mut episode = Episode::new(number, number, position);
+            episode.thumbnail = article
+                .select(&Selector::parse("img").expect("Invalid selector"))
+                .next()
+                .and_then(|img| img.attr("src"))
+                .map(|src| src.to_owned());
+
+            latest.push(LatestEpisode {
+                series: AnimeSummary::new(ID, series_id, tittle),
+                episode,
+            });
+        }
+
+        Ok(latest)
+    }
+
+    fn series(&self, id: &str) -> Result<SeriesDetails> {
+        let text = self.mirrors.get(id)?.text()?;
+        self.parse_series(id, &text)
+    }
+
+    fn episodes(&self, series_id: &str) -> Result<Vec<Episode>> {
+        let text = self.mirrors.get(series_id)?.text()?;
+        parse_episodes(&text)
+    }
+
+    /// The details and the episodes are on the same page
+    fn series_episodes(&self, id: &str) -> Result<(Result<SeriesDetails>, Vec<Episode>)> {
+        let text = self.mirrors.get(id)?.text()?;
+        Ok((self.parse_series(id, &text), parse_episodes(&text)?))
+    }
+
+    fn sources(&self, series_id: &str, episode: &Episode) -> Result<Vec<Source>> {
+        let response = self.mirrors.get(&format!("{}/{}", series_id, episode.id))?;
+        let text = response.text()?;
+
+        // embeds:{SUB:[{server:"MP4Upload",url:"https://..."},...],DUB:[...]}
+        let embeds: BTreeMap<String, Vec<Embed>> = page_data::js_property(&text, "embeds")?;
+        let downloads: BTreeMap<String, Vec<Embed>> =
+            page_data::js_property(&text, "downloads").unwrap_or_default();
+
+        let mut sources = Vec::new();
+        for (kind, groups) in [
+            (SourceKind::Embed, embeds),
+            (SourceKind::Download, downloads),
+        ] {
+            for (language, group) in groups {
+                for embed in group {
+                    sources.push(Source {
+                        server: embed.server,
+                        language: language.clone(),
+                        kind,
+                        url: embed.url,
+                    });
+                }
+            }
+        }
+
+        Ok(sources)
+    }
+}
+
+impl AnimeAv1 {
+    pub fn new(mirrors: Mirrors) -> Self {
+        Self { mirrors }
+    }
+
+    /// Details of the series page
+    fn parse_series(&self, id: &str, text: &str) -> Result<SeriesDetails> {
+        let html = Html::parse_document(text);
+
+        let tittle = html
+            .select(&Selector::parse("h1").expect("Invalid selector"))
+            .next()
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No tittle found (h1)",
+            ))?
+            .text()
+            .next()
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No tittle found (h1)",
+            ))?;
+
+        let mut summary = AnimeSummary::new(ID, id, tittle);
+        summary.cover = html
+            .select(&Selector::parse("figure img").expect("Invalid selector"))
+            .next()
+            .and_then(|img| img.attr("src"))
+            .map(|src| src.to_owned());
+        summary.status = html
+            .select(&Selector::parse("span").expect("Invalid selector"))
+            .find_map(|span| span.text().next().and_then(AnimeStatus::from_label));
+
+        let mut details = SeriesDetails::new(summary);
+        details.synopsis = html
+            .select(&Selector::parse("div.entry p").expect("Invalid selector"))
+            .next()
+            .map(|p| p.text().collect::<String>())
+            .or_else(|| {
+                html.select(&Selector::parse("meta[name=description]").expect("Invalid selector"))
+                    .next()
+                    .and_then(|meta| meta.attr("content"))
+                    .map(str::to_owned)
+            })
+            .map(|synopsis| synopsis.trim().to_owned())
+            .filter(|synopsis| !synopsis.is_empty());
+        details.genres = html
+            .select(&Selector::parse("a[href*=\"genre=\"]").expect("Invalid selector"))
+            .map(|a| a.text().collect::<String>().trim().to_owned())
+            .filter(|genre| !genre.is_empty())
+            .fold(Vec::new(), |mut genres, genre| {
+                if !genres.contains(&genre) {
+                    genres.push(genre);
+                }
+                genres
+            });
+        details.alternative_titles = html
+            .select(&Selector::parse("h1 ~ h2").expect("Invalid selector"))
+            .map(|h2| h2.text().collect::<String>().trim().to_owned())
+            .filter(|title| !title.is_empty())
+            .collect();
+
+        Ok(details)
+    }
+
+    /// Parses a page of the /catalogo listing
+    fn catalog_page(&self, path: &str, page: u32) -> Result<SearchPage> {
+        let response = self.mirrors.get(path)?;
         let html = Html::parse_document(&response.text()?);
 
-        self.series_links.clear();
-        let mut series_names = Vec::new();
+        let mut series = Vec::new();
 
         for article in html.select(&Selector::parse("article").expect("Invalid selector")) {
             let link = article
@@ -25,12 +243,12 @@ impl Client for AnimeAv1 {
                 .next()
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No link found",
+                    "No link found (a)",
                 ))?
                 .attr("href")
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No link found",
+                    "No link found (a)",
                 ))?;
 
             let tittle = article
@@ -38,131 +256,112 @@ impl Client for AnimeAv1 {
                 .next()
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No tittle found",
+                    "No tittle found (h3)",
                 ))?
                 .text()
                 .next()
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No tittle found",
+                    "No tittle found (h3)",
                 ))?;
 
-            self.series_links.push(link.to_owned());
-            series_names.push(tittle.to_owned());
-        }
-
-        Ok(series_names)
-    }
-
-    fn select_anime(&mut self, idx: usize) -> Result<Vec<i32>> {
-        self.name = self
-            .series_links
-            .get(idx)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Invalid index",
-            ))?
-            .to_owned();
-        let url = format!("https://animeav1.com{}", self.name);
-        let response = blocking::get(url)?;
-        let html = Html::parse_document(&response.text()?);
-
-        let mut episodes = Vec::new();
-        for article in
-            html.select(&Selector::parse("article.group\\/item").expect("Invalid selector"))
-        {
-            let tittle = article
-                .select(&Selector::parse("span").expect("Invalid selector"))
+            let mut summary = AnimeSummary::new(ID, link, tittle);
+            summary.cover = article
+                .select(&Selector::parse("img").expect("Invalid selector"))
                 .next()
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "No tittle found",
-                ))?
-                .text()
-                .next()
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "No tittle found",
-                ))?;
-            episodes.push(tittle.parse()?);
-        }
+                .and_then(|img| img.attr("src"))
+                .map(|src| src.to_owned());
+            // Type, year and status are loose labels inside the card, e.g. "TV Anime" or "2023"
+            for label in article
+                .select(&Selector::parse("span, div").expect("Invalid selector"))
+                .filter_map(|element| element.text().next())
+            {
+                if summary.kind.is_none() {
+                    summary.kind = AnimeKind::from_label(label);
+                }
+                if summary.status.is_none() {
+                    summary.status = AnimeStatus::from_label(label);
+                }
+                if summary.year.is_none() && label.trim().len() == 4 {
+                    summary.year = label.trim().parse().ok();
+                }
+            }
 
-        Ok(episodes)
-    }
-
-    fn get_episode_link(&mut self, episode: i32) -> Result<String> {
-        let link = self.default_get_episode_link(episode)?;
-        if CONFIG.read().unwrap().get_frontend() != Frontend::Mpv {
-            return Ok(link);
+            series.push(summary);
         }
 
-        self.get_episode_link_mpv(&link)
+        // The pagination links to the following page while there are more results
+        let next_page = format!("page={}", page + 1);
+        let has_next = html
+            .select(&Selector::parse("a[href]").expect("Invalid selector"))
+            .filter_map(|link| link.attr("href"))
+            .any(|href| href.contains("/catalogo") && href.contains(&next_page));
+
+        Ok(SearchPage {
+            animes: series,
+            page,
+            has_next,
+        })
     }
 }
 
-impl AnimeAv1 {
-    fn default_get_episode_link(&mut self, episode: i32) -> Result<String> {
-        let url = format!("https://animeav1.com{}/{}", self.name, episode);
-        let response = blocking::get(url)?;
-        let text = response.text()?;
-
-        let pattern = r#"embeds:{"#;
-        let start_text_idx = text.find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Episode link not found",
-        ))? + pattern.len();
+/// Episodes of the series page
+fn parse_episodes(text: &str) -> Result<Vec<Episode>> {
+    let html = Html::parse_document(text);
 
-        let pattern = r#"SUB:"#;
-        let start_text_idx = text[start_text_idx..]
-            .find(pattern)
+    let mut episodes = Vec::new();
+    for (position, article) in html
+        .select(&Selector::parse("article.group\\/item").expect("Invalid selector"))
+        .enumerate()
+    {
+        let number = article
+            .select(&Selector::parse("span").expect("Invalid selector"))
+            .next()
             .ok_or(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                "Server not found",
+                "No tittle found (article.group\\/item span)",
             ))?
-            + pattern.len()
-            + start_text_idx;
-
-        let pattern = r#"server:"MP4Upload",url:""#;
-        let start_text_idx = text[start_text_idx..]
-            .find(pattern)
+            .text()
+            .next()
             .ok_or(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                "Server not found",
-            ))?
-            + pattern.len()
-            + start_text_idx;
+                "No tittle found (article.group\\/item span)",
+            ))?;
 
-        let pattern = "\"";
-        let end_idx = text[start_text_idx..]
-            .find(pattern)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Episode link end not found",
-            ))?
-            + start_text_idx;
+        // The link ends with the episode identifier, e.g. /media/one-piece/12
+        let id = article
+            .select(&Selector::parse("a").expect("Invalid selector"))
+            .next()
+            .and_then(|link| link.attr("href"))
+            .and_then(|href| href.rsplit('/').next())
+            .unwrap_or(number);
 
-        Ok(text[start_text_idx..end_idx].to_owned().replace("\\", ""))
+        let mut episode = Episode::new(id, number, position);
+        episode.title = article
+            .select(&Selector::parse("h3, h2").expect("Invalid selector"))
+            .next()
+            .and_then(|title| title.text().next())
+            .map(|title| title.trim().to_owned())
+            .filter(|title| !title.is_empty());
+        episode.thumbnail = article
+            .select(&Selector::parse("img").expect("Invalid selector"))
+            .next()
+            .and_then(|img| img.attr("src"))
+            .map(|src| src.to_owned());
+        // Cards are tagged with their embed groups, untagged ones are only subtitled
+        episode.languages = article
+            .select(&Selector::parse("span").expect("Invalid selector"))
+            .filter_map(|span| span.text().next())
+            .map(str::trim)
+            .filter(|label| LANGUAGE_TAGS.contains(label))
+            .map(str::to_owned)
+            .collect();
+        episode.languages.dedup();
+        if episode.languages.is_empty() {
+            episode.languages.push(LANGUAGES[0].to_owned());
+        }
+        episodes.push(episode);
     }
 
-    fn get_episode_link_mpv(&mut self, link: &str) -> Result<String> {
-        let response = blocking::get(link)?;
-        let text = response.text()?;
-
-        let pattern = r#"src: ""#;
-        let start_text_idx = text.find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Episode link not found",
-        ))? + pattern.len();
-
-        let pattern = "\"";
-        let end_idx = text[start_text_idx..]
-            .find(pattern)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Episode link end not found",
-            ))?
-            + start_text_idx;
-
-        Ok(text[start_text_idx..end_idx].to_owned().replace("\\", ""))
-    }
+    Ok(episodes)
 }
@@ -1,206 +1,389 @@
-use anyhow::{anyhow, Result};
-use headless_chrome::{protocol::cdp::Network, Browser};
-use reqwest::blocking;
+use anyhow::Result;
+use chrono::NaiveDate;
 use scraper::{Html, Selector};
-use std::{
-    sync::{mpsc, Arc},
-    time::Duration,
+use serde::Deserialize;
+use serde_json::Number;
+use std::{collections::BTreeMap, thread};
+
+use crate::{
+    client::{
+        AnimeKind, AnimeStatus, AnimeSummary, Client, Episode, Filters, LatestEpisode,
+        ScheduledEpisode, SearchPage, SeriesDetails, Source, SourceKind,
+    },
+    mirrors::{self, Mirrors},
+    page_data,
 };
 
-use crate::{client::Client, config::CONFIG, frontend::Frontend};
+pub const ID: &str = "AnimeFlv";
+pub const BASE_URL: &str = "https://www3.animeflv.net";
+/// Series pages requested at the same time when building the schedule
+const SCHEDULE_REQUESTS: usize = 8;
 
-#[derive(Default)]
 pub struct AnimeFlv {
-    series_links: Vec<String>,
-    name: String,
+    mirrors: Mirrors,
+}
+
+/// Entry of `var videos` in the episode pages
+#[derive(Deserialize)]
+struct Video {
+    server: String,
+    /// Url of the player
+    code: String,
 }
 
 impl Client for AnimeFlv {
-    fn get_animes(&mut self, query: &str) -> Result<Vec<String>> {
-        let url = format!("https://www3.animeflv.net/browse?q={query}");
-        let response = blocking::get(url)?;
-        let html = Html::parse_document(&response.text()?);
+    fn get_animes(&self, query: &str, page: u32) -> Result<SearchPage> {
+        let path = format!("/browse?q={}&page={page}", mirrors::encode(query));
+        self.catalog_page(&path, page)
+    }
 
-        self.series_links.clear();
-        let mut series_names = Vec::new();
+    fn browse(&self, filters: &Filters, page: u32) -> Result<SearchPage> {
+        // AnimeFlv has no season filter
+        let mut path = format!("/browse?order=default&page={page}");
+        if let Some(genre) = &filters.genre {
+            path.push_str(&format!("&genre[]={genre}"));
+        }
+        if let Some(year) = filters.year {
+            path.push_str(&format!("&year[]={year}"));
+        }
+        if let Some(kind) = filters.kind {
+            let kind = match kind {
+                AnimeKind::Tv | AnimeKind::Ona => "tv",
+                AnimeKind::Movie => "movie",
+                AnimeKind::Ova => "ova",
+                AnimeKind::Special => "special",
+            };
+            path.push_str(&format!("&type[]={kind}"));
+        }
+        if let Some(status) = filters.status {
+            let status = match status {
+                AnimeStatus::Airing => 1,
+                AnimeStatus::Finished => 2,
+                AnimeStatus::Upcoming => 3,
+            };
+            path.push_str(&format!("&status[]={status}"));
+        }
 
-        for article in html.select(&Selector::parse("article").expect("Invalid selector")) {
-            let link = article
-                .select(&Selector::parse("a").expect("Invalid selector"))
-                .next()
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "No link found",
-                ))?
+        self.catalog_page(&path, page)
+    }
+
+    fn latest(&self) -> Result<Vec<LatestEpisode>> {
+        let response = self.mirrors.get("/")?;
+        let html = Html::parse_document(&response.text()?);
+
+        let mut latest = Vec::new();
+        for (position, link) in html
+            .select(&Selector::parse("ul.ListEpisodios li a").expect("Invalid selector"))
+            .enumerate()
+        {
+            // /ver/one-piece-tv-1100 belongs to /anime/one-piece-tv
+            let Some((slug, number)) = link
                 .attr("href")
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "No link found",
-                ))?;
+                .and_then(|href| href.strip_prefix("/ver/"))
+                .and_then(|href| href.rsplit_once('-'))
+            else {
+                continue;
+            };
 
-            let tittle = article
-                .select(&Selector::parse("h3").expect("Invalid selector"))
+            let tittle = link
+                .select(&Selector::parse("strong.Title").expect("Invalid selector"))
                 .next()
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No tittle found",
+                    "No tittle found (strong.Title)",
                 ))?
                 .text()
                 .next()
                 .ok_or(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    "No tittle found",
+                    "No tittle found (strong.Title)",
                 ))?;
 
-            self.series_links.push(link.to_owned());
-            series_names.push(tittle.to_owned());
+            let mut episode = Episode::new(number, number, position);
+            episode.thumbnail = link
+                .select(&Selector::parse("img").expect("Invalid selector"))
+                .next()
+                .and_then(|img| img.attr("src"))
+                .map(|src| format!("{}{src}", self.mirrors.base()));
+
+            latest.push(LatestEpisode {
+                series: AnimeSummary::new(ID, &format!("/anime/{slug}"), tittle),
+                episode,
+            });
         }
 
-        Ok(series_names)
+        Ok(latest)
     }
 
-    fn select_anime(&mut self, idx: usize) -> Result<Vec<i32>> {
-        self.name = self
-            .series_links
-            .get(idx)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Invalid index",
-            ))?
-            .to_owned();
-        let url = format!("https://www3.animeflv.net{}", self.name);
-        let response = blocking::get(url)?;
-        let text = response.text()?;
+    fn schedule(&self) -> Result<Vec<ScheduledEpisode>> {
+        let response = self.mirrors.get("/")?;
+        let html = Html::parse_document(&response.text()?);
 
-        let pattern = "var episodes = ";
-        let start_idx = text.find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Episodes not found",
-        ))? + pattern.len();
-
-        let end_idx = text[start_idx..].find(";").ok_or(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            "End of episodes not found",
-        ))? + start_idx;
-
-        let episodes: Vec<i32> = text[start_idx..end_idx]
-            .trim_matches(&['[', ']'][..])
-            .split("],[")
-            .filter_map(|pair| pair.split(',').next())
-            .filter_map(|num| num.parse::<i32>().ok())
-            .collect();
+        // Sidebar with the series in emission, the date is only on the page of each series
+        let airing = html
+            .select(&Selector::parse("ul.ListSdbr li a").expect("Invalid selector"))
+            .filter_map(|link| {
+                let slug = link.attr("href")?;
+                let tittle = link.text().next()?;
+                let mut summary = AnimeSummary::new(ID, slug, tittle);
+                summary.kind = link
+                    .select(&Selector::parse("span.Type").expect("Invalid selector"))
+                    .next()
+                    .and_then(|span| span.value().classes().find_map(AnimeKind::from_label));
+                summary.status = Some(AnimeStatus::Airing);
+                Some(summary)
+            })
+            .collect::<Vec<_>>();
+
+        let mut schedule = Vec::new();
+        for chunk in airing.chunks(SCHEDULE_REQUESTS) {
+            thread::scope(|scope| {
+                let handles = chunk
+                    .iter()
+                    .map(|summary| (summary, scope.spawn(|| self.series(&summary.slug))))
+                    .collect::<Vec<_>>();
+
+                for (summary, handle) in handles {
+                    let Some(date) = handle
+                        .join()
+                        .ok()
+                        .and_then(Result::ok)
+                        .and_then(|details| details.next_episode)
+                    else {
+                        continue;
+                    };
+                    // The series pages only have the day
+                    schedule.push(ScheduledEpisode {
+                        series: summary.clone(),
+                        date,
+                        time: None,
+                    });
+                }
+            });
+        }
+
+        Ok(schedule)
+    }
+
+    fn series(&self, id: &str) -> Result<SeriesDetails> {
+        let text = self.mirrors.get(id)?.text()?;
+        self.parse_series(id, &text)
+    }
+
+    fn episodes(&self, series_id: &str) -> Result<Vec<Episode>> {
+        let text = self.mirrors.get(series_id)?.text()?;
+        parse_episodes(&text)
+    }
 
-        Ok(episodes)
+    /// The details and the episodes are on the same page
+    fn series_episodes(&self, id: &str) -> Result<(Result<SeriesDetails>, Vec<Episode>)> {
+        let text = self.mirrors.get(id)?.text()?;
+        Ok((self.parse_series(id, &text), parse_episodes(&text)?))
     }
 
-    fn get_episode_link(&mut self, episode: i32) -> Result<String> {
-        if let Ok(link) = self.default_get_episode_link(episode) {
-            return Ok(link);
+    fn sources(&self, series_id: &str, episode: &Episode) -> Result<Vec<Source>> {
+        let path = format!("{}-{}", series_id.replace("anime", "ver"), episode.id);
+        let response = self.mirrors.get(&path)?;
+        let text = response.text()?;
+
+        // {"SUB":[{"server":"sw","title":"SW",...,"code":"https:\/\/..."},...]}
+        let videos: BTreeMap<String, Vec<Video>> = page_data::js_variable(&text, "videos")?;
+        let mut sources = Vec::new();
+        for (language, videos) in videos {
+            for video in videos {
+                sources.push(Source {
+                    server: video.server,
+                    language: language.clone(),
+                    kind: SourceKind::Embed,
+                    url: video.code,
+                });
+            }
         }
 
-        self.get_episode_link_fallback(episode)
+        let html = Html::parse_document(&text);
+        for row in html.select(&Selector::parse("table.Dwnl tbody tr").expect("Invalid selector")) {
+            let cells = row
+                .select(&Selector::parse("td").expect("Invalid selector"))
+                .map(|cell| cell.text().collect::<String>().trim().to_owned())
+                .collect::<Vec<_>>();
+            let Some(link) = row
+                .select(&Selector::parse("a").expect("Invalid selector"))
+                .next()
+                .and_then(|link| link.attr("href"))
+            else {
+                continue;
+            };
+
+            sources.push(Source {
+                server: cells.first().cloned().unwrap_or_default(),
+                language: cells.get(2).cloned().unwrap_or("SUB".to_owned()),
+                kind: SourceKind::Download,
+                url: link.to_owned(),
+            });
+        }
+
+        Ok(sources)
     }
 }
 
 impl AnimeFlv {
-    fn default_get_episode_link(&mut self, episode: i32) -> Result<String> {
-        let url = format!(
-            "https://www3.animeflv.net{}-{}",
-            self.name.replace("anime", "ver"),
-            episode
-        );
-        let response = blocking::get(url)?;
-        let text = response.text()?;
+    pub fn new(mirrors: Mirrors) -> Self {
+        Self { mirrors }
+    }
 
-        let pattern;
-        if CONFIG.read().unwrap().get_frontend() == Frontend::Mpv {
-            pattern = r#""server":"yu""#;
-        } else {
-            pattern = r#""server":"sw""#;
-        }
-        let start_idx = text.find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "SW service not found",
-        ))? + pattern.len();
-
-        let pattern = r#""code":""#;
-        let start_text_idx = text[start_idx..].find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Episode link not found",
-        ))? + pattern.len()
-            + start_idx;
-
-        let pattern = "\"";
-        let end_idx = text[start_text_idx..]
-            .find(pattern)
+    /// Details of the series page
+    fn parse_series(&self, id: &str, text: &str) -> Result<SeriesDetails> {
+        let html = Html::parse_document(text);
+
+        let tittle = html
+            .select(&Selector::parse("h1.Title").expect("Invalid selector"))
+            .next()
             .ok_or(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                "Episode link end not found",
+                "No tittle found (h1.Title)",
             ))?
-            + start_text_idx;
+            .text()
+            .next()
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No tittle found (h1.Title)",
+            ))?;
 
-        Ok(text[start_text_idx..end_idx].to_owned().replace("\\", ""))
+        let mut summary = AnimeSummary::new(ID, id, tittle);
+        summary.cover = html
+            .select(&Selector::parse("div.AnimeCover img").expect("Invalid selector"))
+            .next()
+            .and_then(|img| img.attr("src"))
+            .map(|src| format!("{}{src}", self.mirrors.base()));
+        summary.kind = html
+            .select(&Selector::parse("span.Type").expect("Invalid selector"))
+            .next()
+            .and_then(|span| span.value().classes().find_map(AnimeKind::from_label));
+        summary.status = html
+            .select(&Selector::parse("p.AnmStts span").expect("Invalid selector"))
+            .next()
+            .and_then(|span| span.text().next())
+            .and_then(AnimeStatus::from_label);
+
+        let mut details = SeriesDetails::new(summary);
+        details.synopsis = html
+            .select(&Selector::parse("div.Description p").expect("Invalid selector"))
+            .next()
+            .map(|p| p.text().collect::<String>().trim().to_owned())
+            .filter(|synopsis| !synopsis.is_empty());
+        details.genres = html
+            .select(&Selector::parse("nav.Nvgnrs a").expect("Invalid selector"))
+            .map(|a| a.text().collect::<String>().trim().to_owned())
+            .collect();
+        details.alternative_titles = html
+            .select(&Selector::parse("span.TxtAlt").expect("Invalid selector"))
+            .map(|span| span.text().collect::<String>().trim().to_owned())
+            .collect();
+        details.rating = html
+            .select(&Selector::parse("#votes_prmd").expect("Invalid selector"))
+            .next()
+            .map(|span| span.text().collect::<String>().trim().to_owned())
+            .filter(|rating| !rating.is_empty())
+            .map(|rating| format!("{rating}/5"));
+        // The fourth value of anime_info is only there while the series is airing
+        details.next_episode = anime_info(text)
+            .get(3)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+        Ok(details)
     }
 
-    fn get_episode_link_fallback(&mut self, episode: i32) -> Result<String> {
-        let url = format!(
-            "https://www3.animeflv.net{}-{}",
-            self.name.replace("anime", "ver"),
-            episode
-        );
-        let response = blocking::get(url)?;
-        let text = response.text()?;
+    /// Parses a page of the /browse catalog
+    fn catalog_page(&self, path: &str, page: u32) -> Result<SearchPage> {
+        let response = self.mirrors.get(path)?;
+        let html = Html::parse_document(&response.text()?);
 
-        let pattern = r#""server":"stape""#;
-        let start_idx = text.find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "STAPE service not found",
-        ))? + pattern.len();
-
-        let pattern = r#""code":""#;
-        let start_text_idx = text[start_idx..].find(pattern).ok_or(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Episode link not found",
-        ))? + pattern.len()
-            + start_idx;
-
-        let pattern = "\"";
-        let end_idx = text[start_text_idx..]
-            .find(pattern)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Episode link end not found",
-            ))?
-            + start_text_idx;
-
-        let initial_link = text[start_text_idx..end_idx].to_owned().replace("\\", "");
-
-        let browser = Browser::default()?;
-        let tab = browser.new_tab()?;
-        let target_url = "radosgw";
-        tab.call_method(Network::Enable {
-            max_total_buffer_size: None,
-            max_resource_buffer_size: None,
-            max_post_data_size: None,
-            report_direct_socket_traffic: None,
-            enable_durable_messages: None,
-        })?;
-
-        let (tx, rx) = mpsc::channel();
-        tab.add_event_listener(Arc::new(
-            move |event: &headless_chrome::protocol::cdp::types::Event| match event {
-                headless_chrome::protocol::cdp::types::Event::NetworkResponseReceived(params) => {
-                    if params.params.response.url.contains(target_url) {
-                        // When full url is found return it to main thread
-                        let _ = tx.send(params.params.response.url.clone());
-                    }
-                }
-                _ => {}
-            },
-        ))?;
-        tab.navigate_to(&initial_link)?;
+        let mut series = Vec::new();
+
+        for article in html.select(&Selector::parse("article").expect("Invalid selector")) {
+            let link = article
+                .select(&Selector::parse("a").expect("Invalid selector"))
+                .next()
+                .ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No link found (a)",
+                ))?
+                .attr("href")
+                .ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No link found (a)",
+                ))?;
+
+            let tittle = article
+                .select(&Selector::parse("h3").expect("Invalid selector"))
+                .next()
+                .ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No tittle found (h3)",
+                ))?
+                .text()
+                .next()
+                .ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "No tittle found (h3)",
+                ))?;
 
-        rx.recv_timeout(Duration::from_secs(10))
-            .map_err(|_e| anyhow!("Timed out trying to scrape the episode url from stape"))
+            let mut summary = AnimeSummary::new(ID, link, tittle);
+            summary.cover = article
+                .select(&Selector::parse("img").expect("Invalid selector"))
+                .next()
+                .and_then(|img| img.attr("src"))
+                .map(|src| src.to_owned());
+            // The type is only exposed as a class, e.g. <span class="Type tv">
+            summary.kind = article
+                .select(&Selector::parse("span.Type").expect("Invalid selector"))
+                .next()
+                .and_then(|span| span.value().classes().find_map(AnimeKind::from_label));
+
+            series.push(summary);
+        }
+
+        let has_next = html
+            .select(&Selector::parse("ul.pagination a[rel=next]").expect("Invalid selector"))
+            .next()
+            .is_some();
+
+        Ok(SearchPage {
+            animes: series,
+            page,
+            has_next,
+        })
     }
 }
+
+/// Values of `var anime_info = ["4012","One Piece","one-piece","2024-06-02"];`: anime id, title,
+/// slug and, while airing, the date of the next episode
+fn anime_info(text: &str) -> Vec<String> {
+    page_data::js_variable(text, "anime_info").unwrap_or_default()
+}
+
+/// Episodes of the series page
+fn parse_episodes(text: &str) -> Result<Vec<Episode>> {
+    let info = anime_info(text);
+    let anime_id = info.first();
+
+    // [[1100,54321],[1099,54320],...] with the number first
+    let numbers: Vec<(Number, Number)> = page_data::js_variable(text, "episodes")?;
+    let episodes = numbers
+        .iter()
+        .enumerate()
+        .map(|(position, (number, _))| {
+            let number = number.to_string();
+            let mut episode = Episode::new(&number, &number, position);
+            episode.thumbnail = anime_id.map(|anime_id| {
+                format!(
+                    "https://cdn.animeflv.net/screenshots/{}/{}/th_3.jpg",
+                    anime_id, episode.id
+                )
+            });
+            episode
+        })
+        .collect();
+
+    Ok(episodes)
+}
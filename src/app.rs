@@ -1,18 +1,33 @@
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::prelude::*;
 
 use ratatui::widgets::{Block, Borders, Paragraph};
 use ratatui::DefaultTerminal;
 
-use crate::client::Client;
+use crate::aggregate::{
+    fallback_episode_link, latest_all, schedule_all, search_all, AggregatedAnime,
+};
+use crate::client::{
+    rank_sources, AnimeSummary, Episode, Filters, LatestEpisode, ResolvedEpisode, ScheduledEpisode,
+    SeriesDetails, Source, SourceKind,
+};
 use crate::config::CONFIG;
+use crate::extractor::{Container, Stream};
+use crate::filters::FilterBar;
+use crate::frontend::Frontend;
+use crate::hls::{self, Variant};
 use crate::input::Input;
-use crate::list::OptionsList;
+use crate::list::{schedule_entries, OptionsList};
 use crate::logging::spawn_logger;
-use crate::server::{Server, Servers};
+use crate::provider::PROVIDERS;
+use crate::server::Servers;
+use crate::worker::{Response, Worker};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Default, PartialEq, Eq)]
 enum Focus {
@@ -22,29 +37,82 @@ enum Focus {
     Servers,
 }
 
-#[derive(Default, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 enum Stage {
+    /// Latest episodes of every provider
+    Home,
+    /// Next episodes of the airing series
+    Schedule,
     #[default]
     SeriesSelect,
+    AggregatedResults,
     EpisodeSelect,
+    SourceSelect,
+    /// Renditions of the HLS stream about to be played
+    QualitySelect,
+}
+
+/// Search shown in the list, kept to load its next pages
+struct Search {
+    provider: String,
+    query: Query,
+    page: u32,
+    has_next: bool,
+}
+
+#[derive(Clone)]
+enum Query {
+    Text(String),
+    Browse(Filters),
 }
 
 #[derive(Default)]
 pub struct App {
     exit: bool,
     focus: Focus,
-    client: Box<dyn Client>,
     stage: Stage,
+    aggregated_search: bool,
+    browse_mode: bool,
+    search: Option<Search>,
+    latest: Vec<LatestEpisode>,
+    schedule: Vec<ScheduledEpisode>,
+    aggregated: Vec<AggregatedAnime>,
+    series: Vec<AnimeSummary>,
+    current_series: Option<AnimeSummary>,
+    details: Option<SeriesDetails>,
+    episodes: Vec<Episode>,
+    selected_episode: usize,
+    sources: Vec<Source>,
+    qualities: Vec<Variant>,
+    /// Provider and master playlist of the quality being chosen
+    pending_stream: Option<(String, ResolvedEpisode)>,
+    /// Stage and list to go back to after choosing the quality
+    before_qualities: Option<(Stage, OptionsList)>,
 
     errors: Vec<String>,
+    notice: Option<String>,
 
     input: Input,
+    filter_bar: FilterBar,
     list: OptionsList,
     servers: Servers,
+    worker: Worker,
 }
 
 impl App {
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        self.errors.extend(PROVIDERS.errors().iter().cloned());
+
+        let mut args = std::env::args();
+        match args.any(|arg| arg == "-a" || arg == "--anime") {
+            true => {
+                if let Some(id) = args.next() {
+                    self.handle_open_anime_arg(&id);
+                }
+            }
+            false => self.handle_switch_home(),
+        }
+
         while !self.exit {
             terminal.draw(|frame| self.draw(frame))?;
             self.handle_events()?;
@@ -58,11 +126,20 @@ impl App {
     }
 
     fn handle_events(&mut self) -> Result<()> {
-        match event::read()? {
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event)
+        if event::poll(POLL_INTERVAL)? {
+            match event::read()? {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    self.handle_key_event(key_event)
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(result) = self.worker.poll() {
+            match result {
+                Result::Ok(response) => self.handle_response(response),
+                Err(e) => self.errors.push(e.to_string()),
             }
-            _ => (),
         }
 
         Ok(())
@@ -80,11 +157,41 @@ impl App {
             {
                 self.exit = true
             }
+            KeyCode::Esc if self.worker.is_busy() => self.worker.cancel(),
+            KeyCode::Esc if self.stage == Stage::SourceSelect => self.handle_close_sources(),
+            KeyCode::Esc if self.stage == Stage::QualitySelect => self.handle_close_qualities(),
+            KeyCode::Char('s')
+                if key_event.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.stage == Stage::EpisodeSelect =>
+            {
+                self.handle_open_sources()
+            }
+            KeyCode::Up | KeyCode::Down
+                if key_event.modifiers.contains(KeyModifiers::SHIFT)
+                    && self.stage == Stage::SourceSelect
+                    && self.focus == Focus::List =>
+            {
+                self.handle_move_source(key_event.code == KeyCode::Up)
+            }
             KeyCode::Char('l') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.handle_switch_liked_menu()
             }
+            KeyCode::Char('n') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_switch_home()
+            }
+            KeyCode::Char('e') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_switch_schedule()
+            }
+            KeyCode::Char('b') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.browse_mode = !self.browse_mode;
+                self.set_focus(Focus::Input);
+            }
+            KeyCode::Char('a') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_toggle_aggregated_search()
+            }
             KeyCode::Right | KeyCode::Left
-                if self.stage == Stage::SeriesSelect && self.focus == Focus::List =>
+                if matches!(self.stage, Stage::SeriesSelect | Stage::AggregatedResults)
+                    && self.focus == Focus::List =>
             {
                 self.handle_series_like()
             }
@@ -92,13 +199,16 @@ impl App {
             KeyCode::Tab => self.change_focus_forward(),
             KeyCode::Enter => self.handle_enter(),
             _other => match self.focus {
+                Focus::Input if self.browse_mode => self.filter_bar.handle_key_event(key_event),
                 Focus::Input => self.input.handle_key_event(key_event),
-                Focus::List => self.list.handle_key_event(key_event),
-                Focus::Servers => {
-                    if let Some(client) = self.servers.handle_key_event(key_event) {
-                        self.client = client;
+                Focus::List => {
+                    self.list.handle_key_event(key_event);
+                    // Scrolling past the last result loads the next page
+                    if self.list.is_load_more_selected() {
+                        self.handle_load_more();
                     }
                 }
+                Focus::Servers => self.servers.handle_key_event(key_event),
             },
         }
     }
@@ -111,134 +221,518 @@ impl App {
         }
     }
 
+    fn handle_response(&mut self, response: Response) {
+        match response {
+            Response::Animes(page) => {
+                let Some(search) = &mut self.search else {
+                    return;
+                };
+                search.page = page.page;
+                search.has_next = page.has_next;
+
+                if page.page > 1 {
+                    self.list.append_contents(page.animes.iter().collect());
+                    self.series.extend(page.animes);
+                } else {
+                    self.set_focus(Focus::List);
+                    self.list.set_contents(page.animes.iter().collect());
+                    self.series = page.animes;
+                    self.input.clear();
+                    self.stage = Stage::SeriesSelect;
+                }
+                self.list.set_pagination(
+                    format!("{} resultados · página {}", self.series.len(), page.page),
+                    page.has_next,
+                );
+            }
+            Response::Latest { latest, errors } => {
+                self.set_focus(Focus::List);
+                self.list.set_contents(latest.iter().collect());
+                self.list
+                    .set_pagination("Últimos episodios".to_owned(), false);
+                self.latest = latest;
+                self.stage = Stage::Home;
+                self.errors.extend(errors);
+            }
+            Response::Schedule { schedule, errors } => {
+                self.set_focus(Focus::List);
+                self.list.set_contents(schedule_entries(&schedule));
+                self.list
+                    .set_pagination("Horario de emisión".to_owned(), false);
+                self.schedule = schedule;
+                self.stage = Stage::Schedule;
+                self.errors.extend(errors);
+            }
+            Response::Aggregated { animes, errors } => {
+                self.set_focus(Focus::List);
+                self.list.set_contents(animes.iter().collect());
+                self.aggregated = animes;
+                self.input.clear();
+                self.stage = Stage::AggregatedResults;
+                self.errors.extend(errors);
+            }
+            Response::Episodes {
+                series,
+                details,
+                episodes,
+            } => {
+                self.set_focus(Focus::List);
+                self.list.set_contents(episodes.iter().collect());
+                let mut languages = Vec::new();
+                for language in episodes.iter().flat_map(|episode| &episode.languages) {
+                    if !languages.contains(language) {
+                        languages.push(language.clone());
+                    }
+                }
+                self.servers
+                    .set_series_languages(&series.provider, languages);
+                self.episodes = episodes;
+                self.current_series = Some(series);
+                self.details = details.map(|details| *details);
+                self.stage = Stage::EpisodeSelect;
+            }
+            Response::Sources(mut sources) => {
+                rank_sources(&mut sources);
+                self.set_focus(Focus::List);
+                self.list.set_contents(sources.iter().collect());
+                self.sources = sources;
+                self.stage = Stage::SourceSelect;
+            }
+            Response::EpisodeLink { provider, resolved } => {
+                let name = &PROVIDERS.get(&provider).name;
+                let mut notice = match &self.current_series {
+                    Some(series) if series.provider != provider => format!(
+                        "No disponible en {}, reproduciendo desde {}",
+                        PROVIDERS.get(&series.provider).name,
+                        name
+                    ),
+                    _ => format!("Reproduciendo desde {}", name),
+                };
+                if !resolved.failed.is_empty() {
+                    // Only the first line of each error fits in the status line
+                    let failed: Vec<_> = resolved
+                        .failed
+                        .iter()
+                        .filter_map(|failed| failed.lines().next())
+                        .collect();
+                    notice.push_str(&format!(" · fallaron {}", failed.join(" · ")));
+                }
+                self.notice = Some(notice);
+                self.play(resolved.stream)
+            }
+            Response::Qualities {
+                provider,
+                resolved,
+                variants,
+            } => {
+                self.before_qualities = Some((self.stage, self.list.clone()));
+                self.set_focus(Focus::List);
+                self.list.set_contents(variants.iter().collect());
+                self.list.set_pagination("Calidad".to_owned(), false);
+                self.qualities = variants;
+                self.pending_stream = Some((provider, resolved));
+                self.stage = Stage::QualitySelect;
+            }
+        }
+    }
+
     fn handle_switch_liked_menu(&mut self) {
         let config = CONFIG.read().unwrap();
-        let liked_animes = config.get_liked_animes();
-        self.list
-            .set_contents(liked_animes.iter().cloned().collect());
+        self.series = config
+            .get_liked_animes()
+            .iter()
+            .map(|title| {
+                // Likes saved by older versions only have a title, they are resolved when opened
+                config
+                    .get_liked_series(title)
+                    .cloned()
+                    .unwrap_or_else(|| AnimeSummary::new(config.get_server(), "", title))
+            })
+            .collect();
+        drop(config);
+
+        self.list.set_contents(self.series.iter().collect());
         self.stage = Stage::SeriesSelect;
         self.input.clear();
         self.set_focus(Focus::List);
     }
 
+    fn handle_switch_home(&mut self) {
+        self.worker.spawn("Cargando los últimos episodios", |_| {
+            let (latest, errors) = latest_all()?;
+            Ok(Response::Latest { latest, errors })
+        });
+    }
+
+    fn handle_switch_schedule(&mut self) {
+        self.worker.spawn("Cargando el horario de emisión", |_| {
+            let (schedule, errors) = schedule_all()?;
+            Ok(Response::Schedule { schedule, errors })
+        });
+    }
+
     fn handle_series_like(&mut self) {
-        if let Some(current_selected) = self.list.current_value() {
+        let current_selected = match self.stage {
+            Stage::AggregatedResults => self
+                .list
+                .current()
+                .and_then(|idx| self.aggregated.get(idx))
+                .and_then(|anime| anime.entries.first()),
+            _ => self.list.current().and_then(|idx| self.series.get(idx)),
+        };
+        if let Some(current_selected) = current_selected {
             CONFIG.write().unwrap().toggle_like(current_selected);
         }
     }
 
-    fn handle_enter_input(&mut self) {
-        let animes = match self.client.get_animes(&self.input.content()) {
-            Result::Ok(list) => list,
-            Err(e) => {
-                self.errors.push(e.to_string());
-                return;
+    fn handle_load_more(&mut self) {
+        if self.worker.is_busy() {
+            return;
+        }
+        let Some(search) = self.search.as_ref().filter(|search| search.has_next) else {
+            return;
+        };
+
+        self.spawn_search(
+            search.provider.clone(),
+            search.query.clone(),
+            search.page + 1,
+        );
+    }
+
+    fn spawn_search(&mut self, provider: String, query: Query, page: u32) {
+        let client = PROVIDERS.client(&provider);
+        let description = match page {
+            1 => "Buscando".to_owned(),
+            page => format!("Cargando página {page}"),
+        };
+        self.worker.spawn(&description, move |_| {
+            let page = match &query {
+                Query::Text(text) => client.get_animes(text, page)?,
+                Query::Browse(filters) => client.browse(filters, page)?,
+            };
+            Ok(Response::Animes(page))
+        });
+    }
+
+    fn handle_open_sources(&mut self) {
+        let Some(series) = self.current_series.clone() else {
+            return;
+        };
+        let Some(idx) = self.list.current() else {
+            return;
+        };
+        let Some(episode) = self.episodes.get(idx).cloned() else {
+            return;
+        };
+
+        self.selected_episode = idx;
+        let client = PROVIDERS.client(&series.provider);
+        self.worker.spawn("Cargando servidores", move |_| {
+            Ok(Response::Sources(client.sources(&series.slug, &episode)?))
+        });
+    }
+
+    fn handle_close_qualities(&mut self) {
+        self.pending_stream = None;
+        if let Some((stage, list)) = self.before_qualities.take() {
+            self.stage = stage;
+            self.list = list;
+        }
+    }
+
+    fn handle_close_sources(&mut self) {
+        self.list.set_contents(self.episodes.iter().collect());
+        self.list.select(self.selected_episode);
+        self.stage = Stage::EpisodeSelect;
+    }
+
+    /// Moves the selected source and saves the new order as the preferred servers
+    fn handle_move_source(&mut self, up: bool) {
+        let Some(idx) = self.list.current() else {
+            return;
+        };
+        let target = match up {
+            true if idx > 0 => idx - 1,
+            false if idx + 1 < self.sources.len() => idx + 1,
+            _ => return,
+        };
+        self.sources.swap(idx, target);
+
+        let mut servers = Vec::new();
+        for server in self.sources.iter().map(|source| &source.server) {
+            if !servers.contains(server) {
+                servers.push(server.clone());
+            }
+        }
+        let mut config = CONFIG.write().unwrap();
+        for server in config.get_preferred_servers() {
+            if !servers.contains(&server) {
+                servers.push(server);
             }
+        }
+        config.set_preferred_servers(servers);
+        drop(config);
+
+        self.list.set_contents(self.sources.iter().collect());
+        self.list.select(target);
+    }
+
+    fn handle_toggle_aggregated_search(&mut self) {
+        self.aggregated_search = !self.aggregated_search;
+        self.input.set_title(if self.aggregated_search {
+            "Buscar Anime (todos los servidores)"
+        } else {
+            "Buscar Anime"
+        });
+    }
+
+    fn handle_open_anime_arg(&mut self, id: &str) {
+        let client = PROVIDERS.client(self.servers.current());
+        let id = id.to_owned();
+        self.worker.spawn("Cargando anime", move |_| {
+            let (details, episodes) = client.series_episodes(&id)?;
+            // The summary of the series comes from the details
+            let details = details?;
+            Ok(Response::Episodes {
+                series: details.summary.clone(),
+                details: Some(Box::new(details)),
+                episodes,
+            })
+        });
+    }
+
+    fn open_series(&mut self, series: AnimeSummary) {
+        let client = PROVIDERS.client(&series.provider);
+        self.worker.spawn("Cargando episodios", move |progress| {
+            let series = if series.slug.is_empty() {
+                progress.report(&format!("Buscando \"{}\"", series.title));
+                let found = client
+                    .get_animes(&series.title, 1)?
+                    .animes
+                    .into_iter()
+                    .find(|result| result.title == series.title)
+                    .ok_or(anyhow!("No se ha encontrado \"{}\"", series.title))?;
+                CONFIG.write().unwrap().update_liked_series(&found);
+
+                progress.report("Cargando episodios");
+                found
+            } else {
+                series
+            };
+
+            // The details only fill the side panel, the episodes can be listed without them
+            let (details, episodes) = client.series_episodes(&series.slug)?;
+            Ok(Response::Episodes {
+                series,
+                details: details.ok().map(Box::new),
+                episodes,
+            })
+        });
+    }
+
+    fn handle_enter_input(&mut self) {
+        let query = match self.browse_mode {
+            true => Query::Browse(self.filter_bar.filters().clone()),
+            false => Query::Text(self.input.content()),
         };
+        if self.aggregated_search && !self.browse_mode {
+            let query = self.input.content();
+            self.worker
+                .spawn("Buscando en todos los servidores", move |_| {
+                    let (animes, errors) = search_all(&query)?;
+                    Ok(Response::Aggregated { animes, errors })
+                });
+            return;
+        }
 
-        self.change_focus_forward();
-        self.list.set_contents(animes);
-        self.input.clear();
-        self.stage = Stage::SeriesSelect;
+        let provider = self.servers.current().to_owned();
+        self.search = Some(Search {
+            provider: provider.clone(),
+            query: query.clone(),
+            page: 1,
+            has_next: false,
+        });
+        self.spawn_search(provider, query, 1);
     }
 
     fn handle_enter_list(&mut self) {
         match self.stage {
+            Stage::SeriesSelect if self.list.is_load_more_selected() => self.handle_load_more(),
             Stage::SeriesSelect => {
-                if let Some(selected) = self.list.current() {
-                    let episodes = match self.client.select_anime(selected) {
-                        Result::Ok(episodes) => episodes,
-                        Err(e) => {
-                            self.errors.push(e.to_string());
-                            return;
-                        }
-                    };
-                    self.list
-                        .set_contents(episodes.iter().map(|episode| episode.to_string()).collect());
-                    self.stage = Stage::EpisodeSelect;
+                if let Some(selected) = self.list.current().and_then(|idx| self.series.get(idx)) {
+                    self.open_series(selected.clone());
+                }
+            }
+            Stage::AggregatedResults => {
+                let Some(selected) = self.list.current().and_then(|idx| self.aggregated.get(idx))
+                else {
+                    return;
+                };
+                if let [series] = selected.entries.as_slice() {
+                    self.open_series(series.clone());
+                    return;
                 }
+
+                // Let the user choose the provider
+                self.series = selected.entries.clone();
+                self.list.set_contents(self.series.iter().collect());
+                self.stage = Stage::SeriesSelect;
             }
             Stage::EpisodeSelect => {
-                if let Some(selected) = self.list.current_value() {
-                    let episode_link = match self
-                        .client
-                        .get_episode_link(i32::from_str_radix(&selected, 10).unwrap())
-                    {
-                        Result::Ok(link) => link,
-                        Err(e) => {
-                            self.errors.push(e.to_string());
-                            return;
-                        }
-                    };
-
-                    match CONFIG.read().unwrap().get_frontend() {
-                        crate::frontend::Frontend::Brave => {
-                            match open::with(episode_link, "brave") {
-                                Err(e) => {
-                                    self.errors.push(e.to_string());
-                                    return;
-                                }
-                                _ => (),
-                            };
-                        }
-                        crate::frontend::Frontend::DefaultBrowser => {
-                            match open::that(episode_link) {
-                                Err(e) => {
-                                    self.errors.push(e.to_string());
-                                    return;
-                                }
-                                _ => (),
-                            };
-                        }
-                        crate::frontend::Frontend::Mpv => {
-                            let mut command = Command::new("mpv");
-
-                            if CONFIG.read().unwrap().get_server() == Server::AnimeFlv {
-                                command.args([
-                                    "--ytdl-raw-options=ies=generic",
-                                    "--fullscreen",
-                                    &episode_link,
-                                ]);
-                            }
-                            if CONFIG.read().unwrap().get_server() == Server::AnimeAv1
-                                || CONFIG.read().unwrap().get_server() == Server::AnimeAv1SUB
-                            {
-                                command.args([
-                                    "--http-header-fields=Referer: https://www.mp4upload.com/",
-                                    "--fullscreen",
-                                    &episode_link,
-                                ]);
-                            }
-
-                            match command
-                                .stdout(Stdio::piped())
-                                .stderr(Stdio::piped())
-                                .spawn()
-                            {
-                                Ok(mut cmd) => {
-                                    if let Some(stdout) = cmd.stdout.take() {
-                                        spawn_logger("mpv-backend", stdout, "stdout");
-                                    };
-                                    if let Some(stderr) = cmd.stderr.take() {
-                                        spawn_logger("mpv-backend", stderr, "stderr");
-                                    };
-                                }
-                                Err(e) => {
-                                    self.errors.push(e.to_string());
-                                    return;
-                                }
-                            };
-                        }
-                    }
+                let Some(series) = self.current_series.clone() else {
+                    return;
+                };
+                if let Some(selected) = self.list.current().and_then(|idx| self.episodes.get(idx)) {
+                    self.play_episode(series, selected.clone());
+                }
+            }
+            Stage::Home => {
+                if let Some(selected) = self.list.current().and_then(|idx| self.latest.get(idx)) {
+                    self.current_series = Some(selected.series.clone());
+                    self.details = None;
+                    self.play_episode(selected.series.clone(), selected.episode.clone());
                 }
             }
+            Stage::Schedule => {
+                if let Some(selected) = self.list.current().and_then(|idx| self.schedule.get(idx)) {
+                    self.open_series(selected.series.clone());
+                }
+            }
+            Stage::QualitySelect => {
+                let Some(variant) = self.list.current().and_then(|idx| self.qualities.get(idx))
+                else {
+                    return;
+                };
+                let variant = variant.clone();
+                let Some((provider, mut resolved)) = self.pending_stream.clone() else {
+                    return;
+                };
+                self.handle_close_qualities();
+                resolved.stream = variant.stream(&resolved.stream);
+                self.handle_response(Response::EpisodeLink { provider, resolved });
+            }
+            Stage::SourceSelect => {
+                let Some(series) = &self.current_series else {
+                    return;
+                };
+                let Some(idx) = self.list.current() else {
+                    return;
+                };
+                let Some(selected) = self.sources.get(idx) else {
+                    return;
+                };
+                // The next sources in the language are tried when the selected one fails
+                let sources: Vec<_> = self.sources[idx..]
+                    .iter()
+                    .enumerate()
+                    .filter(|(offset, source)| {
+                        *offset == 0
+                            || (source.language == selected.language
+                                && source.kind == SourceKind::Embed)
+                    })
+                    .map(|(_, source)| source.clone())
+                    .collect();
+                let provider = series.provider.clone();
+                let client = PROVIDERS.client(&provider);
+                let headers = PROVIDERS.get(&provider).headers.clone();
+                self.notice = None;
+                self.worker.spawn(
+                    &format!("Obteniendo el enlace de {}", selected.server),
+                    move |_| {
+                        let resolved = client.resolve_first(&sources, &headers)?;
+                        Ok(episode_link(provider, resolved))
+                    },
+                );
+            }
+        }
+    }
+
+    /// Resolves the link of the episode, trying other providers when it fails
+    fn play_episode(&mut self, series: AnimeSummary, episode: Episode) {
+        let provider = PROVIDERS.get(&series.provider);
+        let client = provider.client.clone();
+        let headers = provider.headers.clone();
+        let preferred = CONFIG.read().unwrap().get_language().to_owned();
+        // Groups outside the list of the provider are only known by the episodes
+        let language = match episode.languages.contains(&preferred) {
+            true => preferred,
+            false => provider.language(&preferred).to_owned(),
+        };
+        self.notice = None;
+        self.worker.spawn("Obteniendo el enlace", move |progress| {
+            let (provider, resolved) =
+                match client.get_episode_link(&series.slug, &episode, &language, &headers) {
+                    Result::Ok(resolved) => (series.provider.clone(), resolved),
+                    Err(e) => fallback_episode_link(&series, &episode, &language, progress)
+                        .map_err(|fallback_errors| {
+                            anyhow!("{}\nOtros servidores:\n{}", e, fallback_errors)
+                        })?,
+                };
+            Ok(episode_link(provider, resolved))
+        });
+    }
+
+    fn play(&mut self, stream: Stream) {
+        match CONFIG.read().unwrap().get_frontend() {
+            Frontend::Brave => {
+                if let Err(e) = open::with(&stream.url, "brave") {
+                    self.errors.push(e.to_string());
+                }
+            }
+            Frontend::DefaultBrowser => {
+                if let Err(e) = open::that(&stream.url) {
+                    self.errors.push(e.to_string());
+                }
+            }
+            Frontend::Mpv => {
+                let mut command = Command::new("mpv");
+
+                if !stream.headers.is_empty() {
+                    command.arg(format!(
+                        "--http-header-fields={}",
+                        stream
+                            .headers
+                            .iter()
+                            .map(|(name, value)| format!("{name}: {value}"))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    ));
+                }
+                if let Some(audio) = &stream.audio {
+                    command.arg(format!("--audio-file={audio}"));
+                }
+                command.args([
+                    "--ytdl-raw-options=ies=generic",
+                    "--fullscreen",
+                    &stream.url,
+                ]);
+
+                match command
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                {
+                    Ok(mut cmd) => {
+                        if let Some(stdout) = cmd.stdout.take() {
+                            spawn_logger("mpv-backend", stdout, "stdout");
+                        };
+                        if let Some(stderr) = cmd.stderr.take() {
+                            spawn_logger("mpv-backend", stderr, "stderr");
+                        };
+                    }
+                    Err(e) => {
+                        self.errors.push(e.to_string());
+                    }
+                };
+            }
         }
     }
 
     fn set_focus(&mut self, focus: Focus) {
         match self.focus {
             Focus::Input => {
-                self.input.defocus();
+                self.set_input_focus(false);
             }
             Focus::List => {
                 self.list.defocus();
@@ -250,7 +744,7 @@ impl App {
 
         match focus {
             Focus::Input => {
-                self.input.focus();
+                self.set_input_focus(true);
             }
             Focus::List => {
                 self.list.focus();
@@ -262,10 +756,21 @@ impl App {
         self.focus = focus;
     }
 
+    /// The search bar and the filters share the same slot
+    fn set_input_focus(&mut self, focus: bool) {
+        if focus {
+            self.input.focus();
+            self.filter_bar.focus();
+        } else {
+            self.input.defocus();
+            self.filter_bar.defocus();
+        }
+    }
+
     fn change_focus_forward(&mut self) {
         match self.focus {
             Focus::Input => {
-                self.input.defocus();
+                self.set_input_focus(false);
                 self.list.focus();
                 self.focus = Focus::List;
             }
@@ -276,7 +781,7 @@ impl App {
             }
             Focus::Servers => {
                 self.servers.defocus();
-                self.input.focus();
+                self.set_input_focus(true);
                 self.focus = Focus::Input;
             }
         }
@@ -285,7 +790,7 @@ impl App {
     fn change_focus_backwards(&mut self) {
         match self.focus {
             Focus::Input => {
-                self.input.defocus();
+                self.set_input_focus(false);
                 self.servers.focus();
                 self.focus = Focus::Servers;
             }
@@ -296,7 +801,7 @@ impl App {
             }
             Focus::List => {
                 self.list.defocus();
-                self.input.focus();
+                self.set_input_focus(true);
                 self.focus = Focus::Input;
             }
         }
@@ -311,13 +816,37 @@ impl Widget for &mut App {
             Constraint::Length(1),
             Constraint::Length(1),
         ]);
-        let [search_area, options_area, _, server_area] = layout.areas(area);
+        let [search_area, options_area, status_area, server_area] = layout.areas(area);
 
         // Search bar
-        self.input.render(search_area, buf);
+        if self.browse_mode {
+            self.filter_bar.render(search_area, buf);
+        } else {
+            self.input.render(search_area, buf);
+        }
+
+        // List, with the details of the series next to the episodes
+        match &self.details {
+            Some(details) if matches!(self.stage, Stage::EpisodeSelect | Stage::SourceSelect) => {
+                let [list_area, details_area] =
+                    Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .areas(options_area);
+                self.list.render(list_area, buf);
+                details.render(details_area, buf);
+            }
+            _ => self.list.render(options_area, buf),
+        }
 
-        // List
-        self.list.render(options_area, buf);
+        // Loading indicator
+        if let Some(status) = self.worker.status() {
+            Line::from(status)
+                .fg(Color::Yellow)
+                .render(status_area, buf);
+        } else if let Some(notice) = &self.notice {
+            Line::from(notice.as_str())
+                .fg(Color::Green)
+                .render(status_area, buf);
+        }
 
         // Server selector
         self.servers.render(server_area, buf);
@@ -355,3 +884,31 @@ impl Widget for &mut App {
         }
     }
 }
+
+/// Response to play the stream. With mpv the quality of HLS master playlists is the preferred
+/// one, or asked when there is none.
+fn episode_link(provider: String, mut resolved: ResolvedEpisode) -> Response {
+    let config = CONFIG.read().unwrap();
+    if resolved.stream.container != Container::Hls || config.get_frontend() != Frontend::Mpv {
+        return Response::EpisodeLink { provider, resolved };
+    }
+    let preferred_quality = config.get_preferred_quality();
+    drop(config);
+
+    // Playing the master playlist leaves the quality to mpv
+    let variants = match hls::variants(&resolved.stream) {
+        Ok(variants) if variants.len() > 1 => variants,
+        _ => return Response::EpisodeLink { provider, resolved },
+    };
+    match preferred_quality.and_then(|height| hls::pick(&variants, height)) {
+        Some(variant) => {
+            resolved.stream = variant.stream(&resolved.stream);
+            Response::EpisodeLink { provider, resolved }
+        }
+        None => Response::Qualities {
+            provider,
+            resolved,
+            variants,
+        },
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use headless_chrome::{
+    protocol::cdp::{types::Event, Network},
+    Browser, LaunchOptions,
+};
+use lazy_static::lazy_static;
+
+/// Without requests the connection to the browser is dropped after this time
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+lazy_static! {
+    /// Headless Chrome shared by the extractors, started on first use
+    static ref SESSION: Mutex<Option<Browser>> = Mutex::new(None);
+}
+
+/// The running browser, starting it again when it was closed or the connection dropped
+fn browser() -> Result<Browser> {
+    let mut session = SESSION.lock().unwrap();
+    if let Some(browser) = session.as_ref() {
+        if browser.get_version().is_ok() {
+            return Ok(browser.clone());
+        }
+    }
+
+    let options = LaunchOptions::default_builder()
+        .idle_browser_timeout(IDLE_TIMEOUT)
+        .build()?;
+    let browser = Browser::new(options)?;
+    *session = Some(browser.clone());
+    Ok(browser)
+}
+
+/// Opens the page in a new tab and returns the url of the first response whose url contains
+/// the pattern
+pub fn sniff(url: &str, pattern: &str, timeout: Duration) -> Result<String> {
+    let tab = browser()?.new_tab()?;
+    tab.call_method(Network::Enable {
+        max_total_buffer_size: None,
+        max_resource_buffer_size: None,
+        max_post_data_size: None,
+        report_direct_socket_traffic: None,
+        enable_durable_messages: None,
+    })?;
+
+    let (tx, rx) = mpsc::channel();
+    let pattern = pattern.to_owned();
+    tab.add_event_listener(Arc::new(move |event: &Event| {
+        if let Event::NetworkResponseReceived(params) = event {
+            if params.params.response.url.contains(&pattern) {
+                let _ = tx.send(params.params.response.url.clone());
+            }
+        }
+    }))?;
+    tab.navigate_to(url)?;
+
+    let result = rx
+        .recv_timeout(timeout)
+        .map_err(|_e| anyhow!("No response matched in {} seconds", timeout.as_secs()));
+    let _ = tab.close(false);
+    result
+}
+
+/// Closes the browser, if it was started
+pub fn shutdown() {
+    SESSION.lock().unwrap().take();
+}
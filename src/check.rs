@@ -0,0 +1,126 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    config::CONFIG,
+    provider::{Provider, Registry},
+};
+
+const DEFAULT_QUERY: &str = "one piece";
+
+/// `--check-providers [--provider <id>] [--base-url <url>] [--query <text>]`
+///
+/// Follows the path of a user (search, series, episodes, servers and link) on every provider
+/// and prints the step that fails with the selector or pattern in the error.
+pub fn run() -> Result<()> {
+    let mut provider_id = None;
+    let mut base_url = None;
+    let mut query = DEFAULT_QUERY.to_owned();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--provider" => provider_id = args.next(),
+            "--base-url" => base_url = args.next(),
+            "--query" => query = args.next().unwrap_or(query),
+            _ => (),
+        }
+    }
+
+    let registry = Registry::with_base_url(base_url.as_deref());
+    for error in registry.errors() {
+        println!("{error}");
+    }
+
+    let providers = registry
+        .all()
+        .iter()
+        .filter(|provider| provider_id.as_ref().is_none_or(|id| *id == provider.id))
+        .collect::<Vec<_>>();
+    if providers.is_empty() {
+        return Err(anyhow!("No existe el proveedor"));
+    }
+
+    let mut failed = Vec::new();
+    for provider in providers {
+        println!("{}", provider.name);
+        if let Err(e) = check_provider(provider, &query) {
+            println!("{e}");
+            failed.push(provider.name.as_str());
+        }
+    }
+
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(anyhow!("Han fallado: {}", failed.join(", "))),
+    }
+}
+
+fn check_provider(provider: &Provider, query: &str) -> Result<()> {
+    let client = &provider.client;
+
+    let page = step("búsqueda", client.get_animes(query, 1))?;
+    let series = step(
+        "búsqueda",
+        page.animes
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("Ningún resultado para \"{query}\"")),
+    )?;
+    pass("búsqueda", &format!("{} ({})", series.title, series.slug));
+
+    let details = step("serie", client.series(&series.slug))?;
+    pass("serie", &details.summary.title);
+
+    let episodes = step("episodios", client.episodes(&series.slug))?;
+    let episode = step(
+        "episodios",
+        episodes
+            .first()
+            .ok_or(anyhow!("La serie no tiene episodios")),
+    )?;
+    pass("episodios", &format!("{} episodios", episodes.len()));
+
+    let sources = step("servidores", client.sources(&series.slug, episode))?;
+    if sources.is_empty() {
+        return step(
+            "servidores",
+            Err(anyhow!("El episodio no tiene servidores")),
+        );
+    }
+    pass(
+        "servidores",
+        &sources
+            .iter()
+            .map(|source| source.server.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+
+    let language = CONFIG.read().unwrap().get_language().to_owned();
+    let resolved = step(
+        "enlace",
+        client.get_episode_link(
+            &series.slug,
+            episode,
+            provider.language(&language),
+            &provider.headers,
+        ),
+    )?;
+    let stream = &resolved.stream;
+    let mut detail = format!("{} ({:?})", stream.url, stream.container);
+    for failed in &resolved.failed {
+        detail.push_str(&format!("\n      ✗ {failed}"));
+    }
+    pass("enlace", &detail);
+
+    Ok(())
+}
+
+fn pass(name: &str, detail: &str) {
+    println!("  ✓ {name}: {detail}");
+}
+
+/// Adds the name of the step to the error
+fn step<T>(name: &str, result: Result<T>) -> Result<T> {
+    result.map_err(|e| anyhow!("  ✗ {name}: {e}"))
+}
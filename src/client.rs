@@ -1,15 +1,379 @@
-use anyhow::Result;
+use std::thread;
 
-use crate::config::CONFIG;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 
-pub trait Client {
-    fn get_animes(&mut self, query: &str) -> Result<Vec<String>>;
-    fn select_anime(&mut self, idx: usize) -> Result<Vec<i32>>;
-    fn get_episode_link(&mut self, idx: i32) -> Result<String>;
+use crate::{
+    config::CONFIG,
+    extractor::{self, Stream},
+};
+
+/// Clients are stateless, every call receives the identifiers it needs so a series can be
+/// opened without searching it first (favorites, command line, other threads...)
+pub trait Client: Send + Sync {
+    /// Pages start at 1
+    fn get_animes(&self, query: &str, page: u32) -> Result<SearchPage>;
+    /// Catalog listing, filters the provider does not support are ignored
+    fn browse(&self, filters: &Filters, page: u32) -> Result<SearchPage>;
+    /// Recently released episodes, newest first
+    fn latest(&self) -> Result<Vec<LatestEpisode>>;
+    /// Airing series with the date of their next episode, providers that do not publish it
+    /// return an empty list
+    fn schedule(&self) -> Result<Vec<ScheduledEpisode>> {
+        Ok(Vec::new())
+    }
+    fn series(&self, id: &str) -> Result<SeriesDetails>;
+    fn episodes(&self, series_id: &str) -> Result<Vec<Episode>>;
+    /// Details and episodes of the series, for opening it. The details are only for showing,
+    /// their error does not stop the episodes from listing.
+    fn series_episodes(&self, id: &str) -> Result<(Result<SeriesDetails>, Vec<Episode>)> {
+        thread::scope(|scope| {
+            let details = scope.spawn(|| self.series(id));
+            let episodes = self.episodes(id)?;
+            let details = details
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("No se pudieron leer los detalles")));
+            Ok((details, episodes))
+        })
+    }
+    /// Every server the episode page lists, in the order of the page
+    fn sources(&self, series_id: &str, episode: &Episode) -> Result<Vec<Source>>;
+    /// Turns a source into what the frontend opens, through the extractor of its host
+    fn resolve(&self, source: &Source) -> Result<Stream> {
+        source.stream()
+    }
+
+    /// Resolves the first source in the language, following the preferred servers order
+    fn get_episode_link(
+        &self,
+        series_id: &str,
+        episode: &Episode,
+        language: &str,
+        headers: &[(String, String)],
+    ) -> Result<ResolvedEpisode> {
+        let mut sources = self.sources(series_id, episode)?;
+        rank_sources(&mut sources);
+
+        let languages = sources.iter().map(|source| source.language.as_str()).fold(
+            Vec::new(),
+            |mut languages, language| {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+                languages
+            },
+        );
+        if !languages.contains(&language) {
+            return Err(anyhow!(
+                "El episodio no está disponible en {}, idiomas disponibles: {}",
+                language,
+                languages.join(", ")
+            ));
+        }
+
+        let sources: Vec<_> = sources
+            .into_iter()
+            .filter(|source| source.language == language && source.kind == SourceKind::Embed)
+            .collect();
+        self.resolve_first(&sources, headers)
+    }
+
+    /// Resolves the sources in order until the host of one of them serves the stream. `headers`
+    /// are the ones of the provider, sent with the stream along with the ones of the host.
+    fn resolve_first(
+        &self,
+        sources: &[Source],
+        headers: &[(String, String)],
+    ) -> Result<ResolvedEpisode> {
+        let mut failed = Vec::new();
+        for source in sources {
+            let result = self.resolve(source).and_then(|stream| {
+                let stream = stream.with_headers(headers);
+                if !stream.probed {
+                    stream.probe()?;
+                }
+                Ok(stream)
+            });
+            match result {
+                Ok(stream) => return Ok(ResolvedEpisode { stream, failed }),
+                Err(e) => failed.push(format!("{}: {}", source.server, e)),
+            }
+        }
+
+        Err(anyhow!("Server not found\n{}", failed.join("\n")))
+    }
+}
+
+/// Sorts the sources by the position of their server in the preferred servers
+pub fn rank_sources(sources: &mut [Source]) {
+    let preferred = CONFIG.read().unwrap().get_preferred_servers();
+    sources.sort_by_key(|source| {
+        preferred
+            .iter()
+            .position(|server| server.eq_ignore_ascii_case(&source.server))
+            .unwrap_or(preferred.len())
+    });
+}
+
+pub struct LatestEpisode {
+    pub series: AnimeSummary,
+    pub episode: Episode,
+}
+
+/// Next episode of an airing series
+pub struct ScheduledEpisode {
+    pub series: AnimeSummary,
+    /// Day of the episode in local time
+    pub date: NaiveDate,
+    /// Air time in local time, for providers that publish it
+    pub time: Option<DateTime<Local>>,
+}
+
+pub struct SearchPage {
+    pub animes: Vec<AnimeSummary>,
+    pub page: u32,
+    pub has_next: bool,
+}
+
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct Filters {
+    /// Slug of the genre, e.g. "accion"
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub season: Option<Season>,
+    pub kind: Option<AnimeKind>,
+    pub status: Option<AnimeStatus>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Season {
+    Winter,
+    Spring,
+    Summer,
+    Fall,
+}
+
+impl Season {
+    pub const ALL: [Self; 4] = [Self::Winter, Self::Spring, Self::Summer, Self::Fall];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Winter => "Invierno",
+            Self::Spring => "Primavera",
+            Self::Summer => "Verano",
+            Self::Fall => "Otoño",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct AnimeSummary {
+    /// Id of the provider in the registry
+    pub provider: String,
+    pub slug: String,
+    pub title: String,
+    pub cover: Option<String>,
+    pub kind: Option<AnimeKind>,
+    pub year: Option<i32>,
+    pub status: Option<AnimeStatus>,
+}
+
+impl AnimeSummary {
+    pub fn new(provider: &str, slug: &str, title: &str) -> Self {
+        Self {
+            provider: provider.to_owned(),
+            slug: slug.to_owned(),
+            title: title.trim().to_owned(),
+            cover: None,
+            kind: None,
+            year: None,
+            status: None,
+        }
+    }
+
+    pub fn badges(&self) -> Vec<String> {
+        let mut badges = Vec::new();
+        if let Some(kind) = self.kind {
+            badges.push(kind.label().to_owned());
+        }
+        if let Some(year) = self.year {
+            badges.push(year.to_string());
+        }
+        if let Some(status) = self.status {
+            badges.push(status.label().to_owned());
+        }
+        badges
+    }
+}
+
+/// Information of the page of a series, the fields the provider does not show are left empty
+#[derive(Clone, PartialEq, Debug)]
+pub struct SeriesDetails {
+    pub summary: AnimeSummary,
+    pub synopsis: Option<String>,
+    pub genres: Vec<String>,
+    pub alternative_titles: Vec<String>,
+    /// Score as displayed by the provider, each one uses its own scale
+    pub rating: Option<String>,
+    /// Release date of the next episode while the series is airing
+    pub next_episode: Option<NaiveDate>,
+}
+
+impl SeriesDetails {
+    pub fn new(summary: AnimeSummary) -> Self {
+        Self {
+            summary,
+            synopsis: None,
+            genres: Vec::new(),
+            alternative_titles: Vec::new(),
+            rating: None,
+            next_episode: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnimeKind {
+    Tv,
+    Movie,
+    Ova,
+    Ona,
+    Special,
+}
+
+impl AnimeKind {
+    pub const ALL: [Self; 5] = [Self::Tv, Self::Movie, Self::Ova, Self::Ona, Self::Special];
+
+    /// Parses the labels and css classes used by the providers ("tv", "Película", "OVA", ...)
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_lowercase();
+        match label.as_str() {
+            "tv" | "anime" | "tv anime" => Some(Self::Tv),
+            "movie" | "película" | "pelicula" => Some(Self::Movie),
+            "ova" => Some(Self::Ova),
+            "ona" => Some(Self::Ona),
+            "special" | "especial" => Some(Self::Special),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Tv => "TV",
+            Self::Movie => "Película",
+            Self::Ova => "OVA",
+            Self::Ona => "ONA",
+            Self::Special => "Especial",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnimeStatus {
+    Airing,
+    Finished,
+    Upcoming,
+}
+
+impl AnimeStatus {
+    pub const ALL: [Self; 3] = [Self::Airing, Self::Finished, Self::Upcoming];
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_lowercase();
+        match label.as_str() {
+            "en emision" | "en emisión" | "emision" | "emisión" => Some(Self::Airing),
+            "finalizado" | "finalizada" => Some(Self::Finished),
+            "proximamente" | "próximamente" => Some(Self::Upcoming),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Airing => "En emisión",
+            Self::Finished => "Finalizado",
+            Self::Upcoming => "Próximamente",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Episode {
+    /// Identifier the provider uses in the episode url
+    pub id: String,
+    /// Number as displayed by the provider, e.g. "12", "12.5" or "Final"
+    pub number: String,
+    pub sort_key: f64,
+    pub title: Option<String>,
+    pub thumbnail: Option<String>,
+    pub air_date: Option<NaiveDate>,
+    /// Audio languages available for this episode, empty when the provider does not tell
+    pub languages: Vec<String>,
+}
+
+impl Episode {
+    /// `position` is used as sort key when the number is not numeric (specials, recaps, ...)
+    pub fn new(id: &str, number: &str, position: usize) -> Self {
+        let number = number.trim();
+        Self {
+            id: id.trim().to_owned(),
+            number: number.to_owned(),
+            sort_key: number.parse().unwrap_or(position as f64),
+            title: None,
+            thumbnail: None,
+            air_date: None,
+            languages: Vec::new(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{} - {}", self.number, title),
+            None => self.number.clone(),
+        }
+    }
+}
+
+/// Stream of an episode and the sources that failed before it, as "server: error"
+#[derive(Clone)]
+pub struct ResolvedEpisode {
+    pub stream: Stream,
+    pub failed: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Source {
+    /// Name the provider gives to the video host, e.g. "sw" or "MP4Upload"
+    pub server: String,
+    pub language: String,
+    pub kind: SourceKind,
+    pub url: String,
+}
+
+impl Source {
+    /// Embeds go through the extractor of their host, downloads are opened as they are
+    pub fn stream(&self) -> Result<Stream> {
+        match self.kind {
+            SourceKind::Embed => extractor::resolve(&self.url),
+            SourceKind::Download => Ok(Stream::page(&self.url)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SourceKind {
+    /// Player page of the video host
+    Embed,
+    /// File hosting page to download the episode
+    Download,
 }
 
-impl Default for Box<dyn Client> {
-    fn default() -> Self {
-        CONFIG.read().unwrap().get_client()
+impl SourceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Embed => "Reproductor",
+            Self::Download => "Descarga",
+        }
     }
 }
@@ -11,15 +11,11 @@ use ratatui::widgets::ListState;
 use ratatui::widgets::Paragraph;
 use ratatui::DefaultTerminal;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::RwLock;
 
-use crate::{
-    client::Client,
-    frontend::Frontend,
-    server::{Server, Servers},
-};
+use crate::{animeav1, client::AnimeSummary, frontend::Frontend, provider::DEFAULT_PROVIDER};
 
 lazy_static! {
     pub static ref CONFIG: RwLock<Config> = Config::empty();
@@ -27,10 +23,27 @@ lazy_static! {
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
-    client: Server,
+    /// Id of the selected provider
+    client: String,
+    /// Preferred audio language, used when the provider has it
+    #[serde(default = "default_language")]
+    language: String,
+    /// Servers to try first, empty uses the default order of the frontend
+    #[serde(default)]
+    preferred_servers: Vec<String>,
+    /// Height of the HLS quality to play, e.g. 720. Without it the quality is asked when there
+    /// are several
+    #[serde(default)]
+    preferred_quality: Option<u32>,
+    /// Site and mirrors of the providers by id, in the order they are tried
+    #[serde(default)]
+    base_urls: BTreeMap<String, Vec<String>>,
     frontend: Frontend,
     log_file_path: PathBuf,
     liked_animes: BTreeSet<String>,
+    /// Identifiers of the liked animes, older configs only stored the titles
+    #[serde(default)]
+    liked_series: Vec<AnimeSummary>,
 }
 
 impl Config {
@@ -41,34 +54,91 @@ impl Config {
 
         let config = std::fs::read_to_string(config_dir.join("config.json")).ok();
         if let Some(config) = config {
-            return serde_json::from_str(&config).ok();
+            let mut config: Self = serde_json::from_str(&config).ok()?;
+
+            // "AnimeAv1 (SUB)" used to be a separate provider serving the dubbed episodes
+            if config.client == "AnimeAv1SUB" {
+                config.client = animeav1::ID.to_owned();
+                config.language = "DUB".to_owned();
+            }
+            return Some(config);
         }
         None
     }
 
+    /// Uses the saved config without asking for it or saving it, for the modes without ui
+    pub fn load() {
+        if let Some(config) = Self::from_file() {
+            *CONFIG.write().unwrap() = config;
+        }
+    }
+
     fn empty() -> RwLock<Self> {
         RwLock::new(Self {
-            client: Server::AnimeAv1,
+            client: DEFAULT_PROVIDER.to_owned(),
+            language: default_language(),
+            preferred_servers: Vec::new(),
+            preferred_quality: None,
+            base_urls: BTreeMap::new(),
             frontend: Frontend::DefaultBrowser,
             log_file_path: PathBuf::new(),
             liked_animes: BTreeSet::new(),
+            liked_series: Vec::new(),
         })
     }
 
-    pub fn get_client(&self) -> Box<dyn Client> {
-        Servers::generate_current_client(&self.client)
+    pub fn get_server(&self) -> &str {
+        &self.client
     }
 
-    pub fn get_server(&self) -> Server {
-        self.client
+    pub fn set_client(&mut self, client: String) {
+        self.client = client;
+
+        self.save();
     }
 
-    pub fn set_client(&mut self, client: Server) {
-        self.client = client;
+    pub fn get_language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn set_language(&mut self, language: String) {
+        self.language = language;
 
         self.save();
     }
 
+    pub fn get_preferred_servers(&self) -> Vec<String> {
+        if !self.preferred_servers.is_empty() {
+            return self.preferred_servers.clone();
+        }
+
+        // mpv can only play the hosts it can extract the video from
+        let servers: &[&str] = match self.frontend {
+            Frontend::Mpv => &["sw", "MP4Upload", "yu", "stape"],
+            Frontend::DefaultBrowser | Frontend::Brave => &["sw", "MP4Upload", "stape"],
+        };
+        servers.iter().map(|server| server.to_string()).collect()
+    }
+
+    pub fn set_preferred_servers(&mut self, servers: Vec<String>) {
+        self.preferred_servers = servers;
+
+        self.save();
+    }
+
+    /// Height of the HLS quality to play without asking
+    pub fn get_preferred_quality(&self) -> Option<u32> {
+        self.preferred_quality
+    }
+
+    /// Empty when the provider uses its own urls
+    pub fn get_base_urls(&self, provider: &str) -> &[String] {
+        self.base_urls
+            .get(provider)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
     pub fn get_log_file(&self) -> &PathBuf {
         &self.log_file_path
     }
@@ -76,14 +146,37 @@ impl Config {
         &self.liked_animes
     }
 
-    pub fn toggle_like(&mut self, series: &str) {
-        if !self.liked_animes.remove(series) {
-            self.liked_animes.insert(series.to_owned());
+    pub fn get_liked_series(&self, title: &str) -> Option<&AnimeSummary> {
+        self.liked_series
+            .iter()
+            .find(|series| series.title == title)
+    }
+
+    pub fn toggle_like(&mut self, series: &AnimeSummary) {
+        if self.liked_animes.remove(&series.title) {
+            self.liked_series
+                .retain(|liked| liked.title != series.title);
+        } else {
+            self.liked_animes.insert(series.title.clone());
+            self.liked_series.push(series.clone());
         }
 
         self.save();
     }
 
+    /// Stores the identifier of a like that was saved only by its title
+    pub fn update_liked_series(&mut self, series: &AnimeSummary) {
+        if !self.liked_animes.contains(&series.title) {
+            return;
+        }
+
+        self.liked_series
+            .retain(|liked| liked.title != series.title);
+        self.liked_series.push(series.clone());
+
+        self.save();
+    }
+
     fn save(&mut self) {
         let dirs = directories::ProjectDirs::from("", "", "ani-cli-es")
             .expect("Could not get the config dir");
@@ -106,6 +199,10 @@ impl Config {
     }
 }
 
+fn default_language() -> String {
+    "SUB".to_owned()
+}
+
 const FRONTENDS: [FrontendData; 3] = [FrontendData {
     frontend: Frontend::DefaultBrowser,
     name: "Navegador Predeterminado",
@@ -155,10 +252,15 @@ impl ConfigApp {
         let dirs = directories::ProjectDirs::from("", "", "ani-cli-es")
             .expect("Could not get the config dir");
         self.config = Some(Config {
-            client: Server::AnimeAv1,
+            client: DEFAULT_PROVIDER.to_owned(),
+            language: default_language(),
+            preferred_servers: Vec::new(),
+            preferred_quality: None,
+            base_urls: BTreeMap::new(),
             frontend: self.run_select_frontend(terminal)?,
             log_file_path: dirs.data_dir().join("logs"),
             liked_animes: BTreeSet::new(),
+            liked_series: Vec::new(),
         });
 
         Ok(())
@@ -218,7 +320,7 @@ impl Widget for &mut ConfigApp {
         title.render(tip_area, buf);
 
         // List
-        if let None = self.frontend_state.selected() {
+        if self.frontend_state.selected().is_none() {
             self.frontend_state.select_first();
         }
         let list = List::new(
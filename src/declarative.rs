@@ -0,0 +1,416 @@
+use std::{collections::BTreeMap, fs, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    client::{
+        AnimeSummary, Client, Episode, Filters, LatestEpisode, SearchPage, SeriesDetails, Source,
+        SourceKind,
+    },
+    mirrors::{self, Mirrors},
+    page_data,
+    provider::{self, Provider},
+};
+
+/// Provider described by a json file of the providers dir, so a broken selector can be fixed
+/// or a site added without recompiling
+#[derive(Deserialize, Clone, Debug)]
+pub struct Definition {
+    pub id: String,
+    pub name: String,
+    /// Replaces `{base}` in the url templates
+    pub base_url: String,
+    /// Used in order when the base url does not answer
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(default = "default_languages")]
+    pub languages: Vec<String>,
+    /// Headers the video hosts need, sent with the streams of the provider
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Url with `{query}` and `{page}`
+    pub search: ListRule,
+    /// Url with `{genre}`, `{year}` and `{page}`, the other filters are ignored
+    pub browse: Option<ListRule>,
+    pub latest: Option<LatestRule>,
+    pub series: SeriesRule,
+    pub episodes: EpisodesRule,
+    pub sources: SourcesRule,
+}
+
+/// Listing of series, each element matching `item` is one result
+#[derive(Deserialize, Clone, Debug)]
+pub struct ListRule {
+    pub url: String,
+    pub item: String,
+    /// Link to the series page, relative to the base url
+    pub link: Extract,
+    pub title: Extract,
+    pub cover: Option<Extract>,
+    /// Selector of the element that is only there when there are more pages
+    pub next_page: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LatestRule {
+    pub url: String,
+    pub item: String,
+    /// Link to the series page, relative to the base url
+    pub link: Extract,
+    pub title: Extract,
+    pub number: Extract,
+}
+
+/// Read from the series page, `{base}{series}`
+#[derive(Deserialize, Clone, Debug)]
+pub struct SeriesRule {
+    pub title: Extract,
+    pub cover: Option<Extract>,
+    pub synopsis: Option<Extract>,
+    pub genres: Option<Extract>,
+}
+
+/// The values of every rule are paired by position
+#[derive(Deserialize, Clone, Debug)]
+pub struct EpisodesRule {
+    /// Url with `{series}`, the series page by default
+    pub url: Option<String>,
+    /// Identifier replaced in `{episode}`
+    pub id: Extract,
+    /// The id is used as number when missing
+    pub number: Option<Extract>,
+    pub title: Option<Extract>,
+}
+
+/// The values of every rule are paired by position
+#[derive(Deserialize, Clone, Debug)]
+pub struct SourcesRule {
+    /// Url with `{series}` and `{episode}`
+    pub url: String,
+    pub server: Extract,
+    /// Player page opened by the frontend
+    pub embed: Extract,
+    /// First language of the provider when missing
+    pub language: Option<String>,
+}
+
+/// Values read from a page or from an element of it
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Extract {
+    /// Css selector, the element itself when missing
+    pub selector: Option<String>,
+    /// Attribute to read instead of the text
+    pub attr: Option<String>,
+    /// Value of `var <name> = ...;` in the scripts of the page as compact json, takes precedence
+    /// over the selector
+    pub variable: Option<String>,
+    /// Keeps every match, the first capture group when there is one
+    pub regex: Option<String>,
+}
+
+impl Extract {
+    fn values(&self, page: &str, scope: ElementRef) -> Result<Vec<String>> {
+        let values = match (&self.variable, &self.selector) {
+            (Some(variable), _) => page_data::js_variable::<Value>(page, variable)
+                .map(|value| value.to_string())
+                .into_iter()
+                .collect(),
+            (None, Some(selector)) => scope
+                .select(&parse_selector(selector)?)
+                .map(|element| self.read(element))
+                .collect(),
+            (None, None) => vec![self.read(scope)],
+        };
+
+        let Some(regex) = &self.regex else {
+            return Ok(values);
+        };
+        let regex = Regex::new(regex)?;
+        Ok(values
+            .iter()
+            .flat_map(|value| {
+                regex
+                    .captures_iter(value)
+                    .filter_map(|captures| captures.get(1).or(captures.get(0)))
+                    .map(|value| value.as_str().to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    fn first(&self, page: &str, scope: ElementRef) -> Result<Option<String>> {
+        Ok(self.values(page, scope)?.into_iter().next())
+    }
+
+    fn read(&self, element: ElementRef) -> String {
+        match &self.attr {
+            Some(attr) => element.attr(attr).unwrap_or_default().to_owned(),
+            None => element.text().collect::<String>().trim().to_owned(),
+        }
+    }
+}
+
+pub struct Declarative {
+    definition: Definition,
+    mirrors: Mirrors,
+}
+
+impl Declarative {
+    pub fn new(definition: Definition, mirrors: Mirrors) -> Self {
+        Self {
+            definition,
+            mirrors,
+        }
+    }
+
+    /// Text of the page of the template, trying the mirrors as `{base}`
+    fn get(&self, template: &str, values: &[(&str, &str)]) -> Result<String> {
+        let response = self.mirrors.fetch(|base| {
+            values
+                .iter()
+                .fold(template.replace("{base}", base), |url, (name, value)| {
+                    url.replace(&format!("{{{name}}}"), value)
+                })
+        })?;
+        Ok(response.text()?)
+    }
+
+    /// Links of the site are kept relative to the base url, as the builtin providers do
+    fn slug(&self, link: &str) -> String {
+        link.strip_prefix(self.mirrors.base())
+            .unwrap_or(link)
+            .to_owned()
+    }
+
+    fn absolute(&self, link: String) -> String {
+        match link.starts_with('/') {
+            true => format!("{}{}", self.mirrors.base(), link),
+            false => link,
+        }
+    }
+
+    fn list_page(&self, rule: &ListRule, values: &[(&str, &str)], page: u32) -> Result<SearchPage> {
+        let text = self.get(&rule.url, values)?;
+        let html = Html::parse_document(&text);
+
+        let mut animes = Vec::new();
+        for item in html.select(&parse_selector(&rule.item)?) {
+            let (Some(link), Some(title)) = (
+                rule.link.first(&text, item)?,
+                rule.title.first(&text, item)?,
+            ) else {
+                continue;
+            };
+            let mut summary = AnimeSummary::new(&self.definition.id, &self.slug(&link), &title);
+            if let Some(cover) = &rule.cover {
+                summary.cover = cover.first(&text, item)?.map(|cover| self.absolute(cover));
+            }
+            animes.push(summary);
+        }
+
+        let has_next = match &rule.next_page {
+            Some(selector) => html.select(&parse_selector(selector)?).next().is_some(),
+            None => false,
+        };
+
+        Ok(SearchPage {
+            animes,
+            page,
+            has_next,
+        })
+    }
+}
+
+impl Client for Declarative {
+    fn get_animes(&self, query: &str, page: u32) -> Result<SearchPage> {
+        let rule = &self.definition.search;
+        let query = mirrors::encode(query);
+        let page_number = page.to_string();
+        self.list_page(rule, &[("query", &query), ("page", &page_number)], page)
+    }
+
+    fn browse(&self, filters: &Filters, page: u32) -> Result<SearchPage> {
+        let rule = self.definition.browse.as_ref().ok_or(anyhow!(
+            "El catálogo de {} no se puede explorar",
+            self.definition.name
+        ))?;
+        let year = filters
+            .year
+            .map(|year| year.to_string())
+            .unwrap_or_default();
+        let page_number = page.to_string();
+        let values = [
+            ("genre", filters.genre.as_deref().unwrap_or_default()),
+            ("year", &year),
+            ("page", &page_number),
+        ];
+        self.list_page(rule, &values, page)
+    }
+
+    fn latest(&self) -> Result<Vec<LatestEpisode>> {
+        let Some(rule) = &self.definition.latest else {
+            return Ok(Vec::new());
+        };
+        let text = self.get(&rule.url, &[])?;
+        let html = Html::parse_document(&text);
+
+        let mut latest = Vec::new();
+        for (position, item) in html.select(&parse_selector(&rule.item)?).enumerate() {
+            let (Some(link), Some(title), Some(number)) = (
+                rule.link.first(&text, item)?,
+                rule.title.first(&text, item)?,
+                rule.number.first(&text, item)?,
+            ) else {
+                continue;
+            };
+            latest.push(LatestEpisode {
+                series: AnimeSummary::new(&self.definition.id, &self.slug(&link), &title),
+                episode: Episode::new(&number, &number, position),
+            });
+        }
+
+        Ok(latest)
+    }
+
+    fn series(&self, id: &str) -> Result<SeriesDetails> {
+        let rule = &self.definition.series;
+        let text = self.get("{base}{series}", &[("series", id)])?;
+        let html = Html::parse_document(&text);
+        let root = html.root_element();
+
+        let title = rule.title.first(&text, root)?.ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No tittle found",
+        ))?;
+        let mut summary = AnimeSummary::new(&self.definition.id, id, &title);
+        if let Some(cover) = &rule.cover {
+            summary.cover = cover.first(&text, root)?.map(|cover| self.absolute(cover));
+        }
+
+        let mut details = SeriesDetails::new(summary);
+        if let Some(synopsis) = &rule.synopsis {
+            details.synopsis = synopsis.first(&text, root)?;
+        }
+        if let Some(genres) = &rule.genres {
+            details.genres = genres.values(&text, root)?;
+        }
+
+        Ok(details)
+    }
+
+    fn episodes(&self, series_id: &str) -> Result<Vec<Episode>> {
+        let rule = &self.definition.episodes;
+        let template = rule.url.as_deref().unwrap_or("{base}{series}");
+        let text = self.get(template, &[("series", series_id)])?;
+        let html = Html::parse_document(&text);
+        let root = html.root_element();
+
+        let ids = rule.id.values(&text, root)?;
+        let numbers = match &rule.number {
+            Some(number) => number.values(&text, root)?,
+            None => ids.clone(),
+        };
+        let titles = match &rule.title {
+            Some(title) => title.values(&text, root)?,
+            None => Vec::new(),
+        };
+
+        Ok(ids
+            .iter()
+            .zip(numbers)
+            .enumerate()
+            .map(|(position, (id, number))| {
+                let mut episode = Episode::new(id, &number, position);
+                episode.title = titles.get(position).cloned();
+                episode
+            })
+            .collect())
+    }
+
+    fn sources(&self, series_id: &str, episode: &Episode) -> Result<Vec<Source>> {
+        let rule = &self.definition.sources;
+        let text = self.get(
+            &rule.url,
+            &[("series", series_id), ("episode", &episode.id)],
+        )?;
+        let html = Html::parse_document(&text);
+        let root = html.root_element();
+
+        let language = rule
+            .language
+            .clone()
+            .or(self.definition.languages.first().cloned())
+            .unwrap_or_else(|| "SUB".to_owned());
+
+        Ok(rule
+            .server
+            .values(&text, root)?
+            .into_iter()
+            .zip(rule.embed.values(&text, root)?)
+            .map(|(server, embed)| Source {
+                server,
+                language: language.clone(),
+                kind: SourceKind::Embed,
+                url: self.absolute(embed.replace("\\/", "/")),
+            })
+            .collect())
+    }
+}
+
+/// Reads every definition of the providers dir, the second value has the files that could not
+/// be loaded. `base_url` replaces the urls of the definitions and the config.
+pub fn load(base_url: Option<&str>) -> (Vec<Provider>, Vec<String>) {
+    let dirs =
+        directories::ProjectDirs::from("", "", "ani-cli-es").expect("Could not get the config dir");
+    let Ok(entries) = fs::read_dir(dirs.config_dir().join("providers")) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut providers = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let definition = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(serde_json::from_str::<Definition>(&text)?));
+        match definition {
+            Ok(definition) => {
+                let urls = std::iter::once(&definition.base_url)
+                    .chain(&definition.mirrors)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                let mirrors = provider::mirrors(&definition.id, base_url, &urls);
+                providers.push(Provider {
+                    id: definition.id.clone(),
+                    name: definition.name.clone(),
+                    languages: definition.languages.clone(),
+                    headers: definition.headers.clone().into_iter().collect(),
+                    client: Arc::new(Declarative::new(definition, mirrors)),
+                })
+            }
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    (providers, errors)
+}
+
+fn parse_selector(selector: &str) -> Result<Selector> {
+    Selector::parse(selector).map_err(|e| anyhow!("Selector inválido \"{selector}\": {e}"))
+}
+
+fn default_languages() -> Vec<String> {
+    vec!["SUB".to_owned()]
+}
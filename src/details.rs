@@ -0,0 +1,71 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph, Wrap},
+};
+use symbols::border;
+
+use crate::client::SeriesDetails;
+
+impl Widget for &SeriesDetails {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from(self.summary.title.as_str()))
+            .border_set(border::PLAIN);
+
+        let mut lines = Vec::new();
+        let badges = self.summary.badges();
+        if !badges.is_empty() {
+            lines.push(Line::from(
+                badges
+                    .into_iter()
+                    .map(|badge| {
+                        Span::raw(format!(" {badge} "))
+                            .fg(Color::Black)
+                            .bg(Color::Cyan)
+                    })
+                    .fold(Vec::new(), |mut spans, badge| {
+                        if !spans.is_empty() {
+                            spans.push(Span::raw(" "));
+                        }
+                        spans.push(badge);
+                        spans
+                    }),
+            ));
+        }
+
+        let fields = [
+            ("Puntuación", self.rating.clone()),
+            (
+                "Próximo episodio",
+                self.next_episode
+                    .map(|date| date.format("%d/%m/%Y").to_string()),
+            ),
+            (
+                "Géneros",
+                Some(self.genres.join(", ")).filter(|genres| !genres.is_empty()),
+            ),
+            (
+                "También conocido como",
+                Some(self.alternative_titles.join(", ")).filter(|titles| !titles.is_empty()),
+            ),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("{name}: ")).add_modifier(Modifier::BOLD),
+                    Span::raw(value),
+                ]));
+            }
+        }
+
+        if let Some(synopsis) = &self.synopsis {
+            lines.push(Line::default());
+            lines.push(Line::from(synopsis.as_str()));
+        }
+
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use reqwest::{blocking, header::RANGE, Method, Url};
+
+use crate::{
+    config::CONFIG, frontend::Frontend, mp4upload::Mp4Upload, streamtape::Streamtape,
+    streamwish::StreamWish,
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref EXTRACTORS: Vec<Box<dyn Extractor>> = vec![
+        Box::new(Mp4Upload),
+        Box::new(Streamtape),
+        Box::new(StreamWish)
+    ];
+}
+
+/// What the frontend opens for an episode
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Stream {
+    pub url: String,
+    /// Headers the host needs to serve the video
+    pub headers: Vec<(String, String)>,
+    pub container: Container,
+    /// Separate audio track, for HLS renditions without audio
+    pub audio: Option<String>,
+    /// The extractor already checked that the host serves it, it is not probed again
+    pub probed: bool,
+}
+
+impl Stream {
+    /// The embed page itself, when the video can not be extracted
+    pub fn page(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            headers: Vec::new(),
+            container: Container::Page,
+            audio: None,
+            probed: false,
+        }
+    }
+
+    /// Adds the headers the stream does not set already, the ones of the host win
+    pub fn with_headers(mut self, headers: &[(String, String)]) -> Self {
+        for (name, value) in headers {
+            if !self
+                .headers
+                .iter()
+                .any(|(other, _)| other.eq_ignore_ascii_case(name))
+            {
+                self.headers.push((name.clone(), value.clone()));
+            }
+        }
+        self
+    }
+
+    /// Checks that the host serves the stream with its headers, so dead links are skipped
+    /// before the player opens them
+    pub fn probe(&self) -> Result<()> {
+        let client = blocking::Client::builder().timeout(PROBE_TIMEOUT).build()?;
+        let request = |method: Method| {
+            self.headers.iter().fold(
+                client.request(method, &self.url),
+                |request, (name, value)| request.header(name, value),
+            )
+        };
+
+        if let Ok(response) = request(Method::HEAD).send() {
+            if response.status().is_success() {
+                return Ok(());
+            }
+        }
+        // Some hosts do not answer HEAD requests, the first byte is enough
+        let status = request(Method::GET)
+            .header(RANGE, "bytes=0-0")
+            .send()?
+            .status();
+        match status.is_success() {
+            true => Ok(()),
+            false => Err(anyhow!("El enlace no responde ({status})")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Container {
+    /// Player page of the host, mpv can only play it through yt-dlp
+    Page,
+    Mp4,
+    Hls,
+}
+
+impl Container {
+    /// By the extension of the path, links of unknown hosts are usually pages
+    pub fn from_url(url: &str) -> Self {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        if path.ends_with(".m3u8") {
+            Self::Hls
+        } else if path.ends_with(".mp4") {
+            Self::Mp4
+        } else {
+            Self::Page
+        }
+    }
+}
+
+/// Turns the embed links of a video host into the video, whatever provider listed them
+pub trait Extractor: Send + Sync {
+    /// Domains of the host, subdomains included
+    fn hosts(&self) -> &[&str];
+    fn extract(&self, url: &str) -> Result<Stream>;
+    /// The host only serves the video with headers, browsers can not play it
+    fn needs_headers(&self) -> bool {
+        false
+    }
+}
+
+/// Extractor of the host of the embed link
+pub fn find(url: &str) -> Option<&'static dyn Extractor> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    EXTRACTORS
+        .iter()
+        .find(|extractor| {
+            extractor
+                .hosts()
+                .iter()
+                .any(|domain| host == *domain || host.ends_with(&format!(".{domain}")))
+        })
+        .map(Box::as_ref)
+}
+
+/// The video of the embed link when its host is known. Browsers get the embed page when the
+/// video needs headers they can not send or the extraction fails.
+pub fn resolve(url: &str) -> Result<Stream> {
+    let Some(extractor) = find(url) else {
+        return Ok(Stream {
+            container: Container::from_url(url),
+            ..Stream::page(url)
+        });
+    };
+
+    if CONFIG.read().unwrap().get_frontend() == Frontend::Mpv {
+        return extractor.extract(url);
+    }
+    if extractor.needs_headers() {
+        return Ok(Stream::page(url));
+    }
+    match extractor.extract(url) {
+        Ok(stream) if stream.headers.is_empty() => Ok(stream),
+        _ => Ok(Stream::page(url)),
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::{Datelike, Local};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph},
+};
+use symbols::border;
+
+use crate::client::{AnimeKind, AnimeStatus, Filters, Season};
+
+/// Genre slugs shared by the catalogs of the providers
+const GENRES: [(&str, &str); 40] = [
+    ("accion", "Acción"),
+    ("artes-marciales", "Artes Marciales"),
+    ("aventura", "Aventura"),
+    ("carreras", "Carreras"),
+    ("ciencia-ficcion", "Ciencia Ficción"),
+    ("comedia", "Comedia"),
+    ("demencia", "Demencia"),
+    ("demonios", "Demonios"),
+    ("deportes", "Deportes"),
+    ("drama", "Drama"),
+    ("ecchi", "Ecchi"),
+    ("escolares", "Escolares"),
+    ("espacial", "Espacial"),
+    ("fantasia", "Fantasía"),
+    ("harem", "Harem"),
+    ("historico", "Histórico"),
+    ("infantil", "Infantil"),
+    ("josei", "Josei"),
+    ("juegos", "Juegos"),
+    ("magia", "Magia"),
+    ("mecha", "Mecha"),
+    ("militar", "Militar"),
+    ("misterio", "Misterio"),
+    ("musica", "Música"),
+    ("parodia", "Parodia"),
+    ("policia", "Policía"),
+    ("psicologico", "Psicológico"),
+    ("recuentos-de-la-vida", "Recuentos de la vida"),
+    ("romance", "Romance"),
+    ("samurai", "Samurai"),
+    ("seinen", "Seinen"),
+    ("shoujo", "Shoujo"),
+    ("shounen", "Shounen"),
+    ("sobrenatural", "Sobrenatural"),
+    ("superpoderes", "Superpoderes"),
+    ("suspenso", "Suspenso"),
+    ("terror", "Terror"),
+    ("vampiros", "Vampiros"),
+    ("yaoi", "Yaoi"),
+    ("yuri", "Yuri"),
+];
+const FIRST_YEAR: i32 = 1970;
+const FIELDS: usize = 5;
+
+/// Catalog filters shown instead of the search bar in browse mode
+#[derive(Default)]
+pub struct FilterBar {
+    filters: Filters,
+    field: usize,
+
+    focus: bool,
+}
+
+impl FilterBar {
+    pub fn focus(&mut self) {
+        self.focus = true;
+    }
+
+    pub fn defocus(&mut self) {
+        self.focus = false;
+    }
+
+    pub fn filters(&self) -> &Filters {
+        &self.filters
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Left => self.field = (self.field + FIELDS - 1) % FIELDS,
+            KeyCode::Right => self.field = (self.field + 1) % FIELDS,
+            KeyCode::Up => self.change_value(false),
+            KeyCode::Down => self.change_value(true),
+            KeyCode::Backspace | KeyCode::Delete => self.clear_value(),
+            _ => (),
+        }
+    }
+
+    fn change_value(&mut self, forward: bool) {
+        let filters = &mut self.filters;
+        match self.field {
+            0 => {
+                let genres = GENRES.map(|(slug, _)| slug);
+                filters.genre =
+                    cycle(&genres, filters.genre.as_deref(), forward).map(str::to_owned);
+            }
+            1 => {
+                let years = (FIRST_YEAR..=Local::now().year()).rev().collect::<Vec<_>>();
+                filters.year = cycle(&years, filters.year, forward);
+            }
+            2 => filters.season = cycle(&Season::ALL, filters.season, forward),
+            3 => filters.kind = cycle(&AnimeKind::ALL, filters.kind, forward),
+            _ => filters.status = cycle(&AnimeStatus::ALL, filters.status, forward),
+        }
+    }
+
+    fn clear_value(&mut self) {
+        let filters = &mut self.filters;
+        match self.field {
+            0 => filters.genre = None,
+            1 => filters.year = None,
+            2 => filters.season = None,
+            3 => filters.kind = None,
+            _ => filters.status = None,
+        }
+    }
+
+    fn values(&self) -> [(&'static str, String); FIELDS] {
+        let filters = &self.filters;
+        let genre = filters.genre.as_ref().map(|genre| {
+            GENRES
+                .iter()
+                .find(|(slug, _)| slug == genre)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or(genre.clone())
+        });
+
+        [
+            ("Género", genre),
+            ("Año", filters.year.map(|year| year.to_string())),
+            ("Temporada", filters.season.map(|s| s.label().to_owned())),
+            ("Tipo", filters.kind.map(|kind| kind.label().to_owned())),
+            ("Estado", filters.status.map(|s| s.label().to_owned())),
+        ]
+        .map(|(name, value)| (name, value.unwrap_or("Todos".to_owned())))
+    }
+}
+
+/// Next or previous option, `None` sits before the first and after the last one
+fn cycle<T: Copy + PartialEq>(options: &[T], current: Option<T>, forward: bool) -> Option<T> {
+    let idx = current.and_then(|current| options.iter().position(|option| *option == current));
+    let next = match (idx, forward) {
+        (None, true) => Some(0),
+        (None, false) => options.len().checked_sub(1),
+        (Some(idx), true) => (idx + 1 < options.len()).then_some(idx + 1),
+        (Some(idx), false) => idx.checked_sub(1),
+    };
+    next.map(|idx| options[idx])
+}
+
+impl Widget for &FilterBar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from("Explorar catálogo"))
+            .title_bottom(Line::from("<←→ filtro, ↑↓ valor, enter para buscar>").right_aligned())
+            .border_set(border::PLAIN);
+
+        let mut spans = Vec::new();
+        for (idx, (name, value)) in self.values().into_iter().enumerate() {
+            if idx > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::raw(format!("{name}: ")));
+            let value = Span::raw(value);
+            spans.push(if self.focus && idx == self.field {
+                value.fg(Color::Black).bg(Color::Yellow)
+            } else {
+                value.add_modifier(Modifier::BOLD)
+            });
+        }
+
+        Paragraph::new(Line::from(spans))
+            .block(block)
+            .fg(match self.focus {
+                true => Color::Yellow,
+                false => Color::White,
+            })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use reqwest::{blocking, Url};
+
+use crate::extractor::{Container, Stream};
+
+/// Rendition of a master playlist
+#[derive(Clone, Debug)]
+pub struct Variant {
+    pub url: String,
+    /// Bits per second
+    pub bandwidth: u64,
+    pub resolution: Option<(u32, u32)>,
+    /// Group of the audio renditions the variant plays with
+    pub audio_group: Option<String>,
+    /// Playlist of the default audio of the group, when the audio is not in the variant
+    pub audio_url: Option<String>,
+}
+
+impl Variant {
+    pub fn height(&self) -> Option<u32> {
+        self.resolution.map(|(_, height)| height)
+    }
+
+    /// e.g. "1080p" or "2400 kbps"
+    pub fn label(&self) -> String {
+        match self.height() {
+            Some(height) => format!("{height}p"),
+            None => format!("{} kbps", self.bandwidth / 1000),
+        }
+    }
+
+    /// The stream of the variant, with the headers of the master playlist
+    pub fn stream(&self, master: &Stream) -> Stream {
+        Stream {
+            url: self.url.clone(),
+            headers: master.headers.clone(),
+            container: Container::Hls,
+            audio: self.audio_url.clone(),
+            probed: false,
+        }
+    }
+}
+
+/// The best variant up to the preferred height, or the worst one with video when all are above
+/// it. Variants without resolution (audio only) are only picked when there is nothing else.
+pub fn pick(variants: &[Variant], preferred_height: u32) -> Option<&Variant> {
+    variants
+        .iter()
+        .find(|variant| {
+            variant
+                .height()
+                .is_some_and(|height| height <= preferred_height)
+        })
+        .or_else(|| variants.iter().rfind(|variant| variant.height().is_some()))
+        .or(variants.last())
+}
+
+/// Variants of the master playlist from the best to the worst, empty when the playlist is a
+/// media playlist with a single quality
+pub fn variants(stream: &Stream) -> Result<Vec<Variant>> {
+    let mut request = blocking::Client::new().get(&stream.url);
+    for (name, value) in &stream.headers {
+        request = request.header(name, value);
+    }
+    let response = request.send()?.error_for_status()?;
+    // Relative uris are relative to the playlist after the redirects
+    let base = response.url().clone();
+    parse(&response.text()?, &base)
+}
+
+fn parse(playlist: &str, base: &Url) -> Result<Vec<Variant>> {
+    let mut variants = Vec::new();
+    // (group, uri) of the audio renditions, the default one of each group first
+    let mut audios = Vec::new();
+    let mut lines = playlist.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attributes = attributes_of(attributes);
+            let get = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.clone())
+            };
+            if let (Some("AUDIO"), Some(group), Some(uri)) =
+                (get("TYPE").as_deref(), get("GROUP-ID"), get("URI"))
+            {
+                let audio = (group, base.join(&uri)?.to_string());
+                match get("DEFAULT").as_deref() {
+                    Some("YES") => audios.insert(0, audio),
+                    _ => audios.push(audio),
+                }
+            }
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let Some(uri) = lines.find(|line| !line.is_empty() && !line.starts_with('#')) else {
+                break;
+            };
+            let mut variant = Variant {
+                url: base.join(uri)?.to_string(),
+                bandwidth: 0,
+                resolution: None,
+                audio_group: None,
+                audio_url: None,
+            };
+            for (key, value) in attributes_of(attributes) {
+                match key.as_str() {
+                    "BANDWIDTH" => variant.bandwidth = value.parse().unwrap_or_default(),
+                    "RESOLUTION" => {
+                        variant.resolution = value.split_once('x').and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                    }
+                    "AUDIO" => variant.audio_group = Some(value),
+                    _ => (),
+                }
+            }
+            variants.push(variant);
+        }
+    }
+
+    for variant in &mut variants {
+        variant.audio_url = audios
+            .iter()
+            .find(|(group, _)| Some(group) == variant.audio_group.as_ref())
+            .map(|(_, uri)| uri.clone());
+    }
+    variants.sort_by_key(|variant| std::cmp::Reverse((variant.height(), variant.bandwidth)));
+    Ok(variants)
+}
+
+/// `KEY=value,KEY="quoted, value"` of a tag
+fn attributes_of(text: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = text;
+    while let Some((key, value)) = rest.split_once('=') {
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        attributes.push((key.trim().to_owned(), value.to_owned()));
+        rest = next.trim_start_matches(',');
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER: &str = r#"#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aud",NAME="Japonés, original",DEFAULT=NO,URI="audio/en.m3u8"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aud",NAME="Japonés",DEFAULT=YES,URI="audio/ja.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS="avc1.4d401e,mp4a.40.2",AUDIO="aud"
+360/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS="mp4a.40.2"
+audio-only.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080,AUDIO="aud"
+
+https://cdn.example.com/1080.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720
+720/index.m3u8
+"#;
+
+    fn variants() -> Vec<Variant> {
+        let base = Url::parse("https://host.example/hls/master.m3u8?t=1").unwrap();
+        parse(MASTER, &base).unwrap()
+    }
+
+    #[test]
+    fn parses_variants_from_best_to_worst() {
+        let variants = variants();
+        let labels: Vec<_> = variants.iter().map(Variant::label).collect();
+        assert_eq!(labels, ["1080p", "720p", "360p", "64 kbps"]);
+
+        assert_eq!(variants[0].url, "https://cdn.example.com/1080.m3u8");
+        assert_eq!(variants[0].bandwidth, 5_000_000);
+        assert_eq!(variants[1].url, "https://host.example/hls/720/index.m3u8");
+        assert_eq!(variants[2].resolution, Some((640, 360)));
+    }
+
+    #[test]
+    fn uses_the_default_audio_of_the_group() {
+        let variants = variants();
+        assert_eq!(variants[0].audio_group.as_deref(), Some("aud"));
+        assert_eq!(
+            variants[0].audio_url.as_deref(),
+            Some("https://host.example/hls/audio/ja.m3u8")
+        );
+        assert_eq!(variants[1].audio_url, None);
+    }
+
+    #[test]
+    fn quoted_values_keep_their_commas() {
+        let attributes = attributes_of(r#"BANDWIDTH=1,CODECS="avc1,mp4a",AUDIO="aud""#);
+        assert_eq!(
+            attributes,
+            [
+                ("BANDWIDTH".to_owned(), "1".to_owned()),
+                ("CODECS".to_owned(), "avc1,mp4a".to_owned()),
+                ("AUDIO".to_owned(), "aud".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn picks_the_best_up_to_the_preferred_height() {
+        let variants = variants();
+        let pick = |height| pick(&variants, height).map(Variant::label);
+        assert_eq!(pick(1080).as_deref(), Some("1080p"));
+        assert_eq!(pick(900).as_deref(), Some("720p"));
+        // Below every variant the worst one with video is used, never the audio only one
+        assert_eq!(pick(240).as_deref(), Some("360p"));
+    }
+
+    #[test]
+    fn media_playlists_have_no_variants() {
+        let base = Url::parse("https://host.example/index.m3u8").unwrap();
+        let playlist = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:10,\nseg0.ts\n";
+        assert!(parse(playlist, &base).unwrap().is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use ratatui::{
 use symbols::border;
 
 pub struct Input {
+    title: String,
     content: Vec<char>,
     index: usize,
 
@@ -15,6 +16,7 @@ pub struct Input {
 impl Default for Input {
     fn default() -> Self {
         Self {
+            title: "Buscar Anime".to_owned(),
             content: Vec::default(),
             index: usize::default(),
             focus: true,
@@ -100,6 +102,10 @@ impl Input {
         self.focus = false;
     }
 
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_owned();
+    }
+
     pub fn clear(&mut self) {
         self.content.clear();
         self.index = 0;
@@ -109,7 +115,7 @@ impl Input {
 impl Widget for &Input {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let block = Block::bordered()
-            .title(Line::from("Buscar Anime"))
+            .title(Line::from(self.title.as_str()))
             .border_set(border::PLAIN);
         let counter_text = Text::from(self.render_content());
         Paragraph::new(counter_text)
@@ -3,19 +3,156 @@ use std::{
     time::{Duration, Instant},
 };
 
+use chrono::{Datelike, Local, NaiveDate, Weekday};
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget},
 };
 
-use crate::config::CONFIG;
+use crate::{
+    aggregate::AggregatedAnime,
+    client::{AnimeSummary, Episode, LatestEpisode, ScheduledEpisode, Source},
+    config::CONFIG,
+    hls::Variant,
+    provider::PROVIDERS,
+};
 
 const SEARCH_BUFFER_RESET_DURATION: Duration = Duration::from_millis(700);
 
+#[derive(Clone)]
+pub struct ListEntry {
+    label: String,
+    badges: Vec<String>,
+    /// Title of the entries below it, it can not be selected
+    heading: bool,
+}
+
+impl ListEntry {
+    fn heading(label: String) -> Self {
+        Self {
+            label,
+            badges: Vec::new(),
+            heading: true,
+        }
+    }
+}
+
+impl From<String> for ListEntry {
+    fn from(label: String) -> Self {
+        Self {
+            label,
+            badges: Vec::new(),
+            heading: false,
+        }
+    }
+}
+
+impl From<&AnimeSummary> for ListEntry {
+    fn from(summary: &AnimeSummary) -> Self {
+        let mut badges = summary.badges();
+        badges.insert(0, PROVIDERS.get(&summary.provider).name.clone());
+        Self {
+            label: summary.title.clone(),
+            badges,
+            heading: false,
+        }
+    }
+}
+
+impl From<&LatestEpisode> for ListEntry {
+    fn from(latest: &LatestEpisode) -> Self {
+        Self {
+            label: format!(
+                "{} - Episodio {}",
+                latest.series.title, latest.episode.number
+            ),
+            badges: vec![PROVIDERS.get(&latest.series.provider).name.clone()],
+            heading: false,
+        }
+    }
+}
+
+impl From<&ScheduledEpisode> for ListEntry {
+    fn from(scheduled: &ScheduledEpisode) -> Self {
+        Self {
+            label: scheduled.series.title.clone(),
+            badges: scheduled
+                .time
+                .iter()
+                .map(|time| time.format("%H:%M").to_string())
+                .chain([PROVIDERS.get(&scheduled.series.provider).name.clone()])
+                .collect(),
+            heading: false,
+        }
+    }
+}
+
+impl From<&AggregatedAnime> for ListEntry {
+    fn from(anime: &AggregatedAnime) -> Self {
+        Self {
+            label: anime.title.clone(),
+            badges: anime
+                .entries
+                .iter()
+                .map(|entry| {
+                    let provider = PROVIDERS.get(&entry.provider);
+                    format!("{} {}", provider.name, provider.languages.join("/"))
+                })
+                .collect(),
+            heading: false,
+        }
+    }
+}
+
+impl From<&Episode> for ListEntry {
+    fn from(episode: &Episode) -> Self {
+        Self {
+            label: episode.label(),
+            badges: episode
+                .air_date
+                .iter()
+                .map(|date| date.format("%d/%m/%Y").to_string())
+                .chain(episode.languages.iter().cloned())
+                .collect(),
+            heading: false,
+        }
+    }
+}
+
+impl From<&Variant> for ListEntry {
+    fn from(variant: &Variant) -> Self {
+        Self {
+            label: variant.label(),
+            badges: [format!(
+                "{:.1} Mbps",
+                variant.bandwidth as f64 / 1_000_000.0
+            )]
+            .into_iter()
+            .chain(variant.audio_group.clone())
+            .collect(),
+            heading: false,
+        }
+    }
+}
+
+impl From<&Source> for ListEntry {
+    fn from(source: &Source) -> Self {
+        Self {
+            label: source.server.clone(),
+            badges: vec![source.language.clone(), source.kind.label().to_owned()],
+            heading: false,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct OptionsList {
-    contents: Vec<String>,
+    contents: Vec<ListEntry>,
+    title: Option<String>,
+    /// Shows a last entry to load the next page
+    load_more: bool,
     list_state: ListState,
     focus: bool,
     search_buffer: String,
@@ -31,9 +168,34 @@ impl OptionsList {
         self.focus = false;
     }
 
-    pub fn set_contents(&mut self, contents: Vec<String>) {
-        self.contents = contents;
+    pub fn set_contents<T: Into<ListEntry>>(&mut self, contents: Vec<T>) {
+        self.contents = contents.into_iter().map(Into::into).collect();
+        self.title = None;
+        self.load_more = false;
         self.list_state.select_first();
+        self.skip_headings(true);
+    }
+
+    /// Adds entries at the end keeping the selection
+    pub fn append_contents<T: Into<ListEntry>>(&mut self, contents: Vec<T>) {
+        self.contents.extend(contents.into_iter().map(Into::into));
+    }
+
+    pub fn set_pagination(&mut self, title: String, load_more: bool) {
+        self.title = Some(title);
+        self.load_more = load_more;
+    }
+
+    pub fn is_load_more_selected(&self) -> bool {
+        self.load_more
+            && self
+                .list_state
+                .selected()
+                .is_some_and(|idx| idx >= self.contents.len())
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.list_state.select(Some(idx));
     }
 
     pub fn handle_key_event(&mut self, key_event: KeyEvent) {
@@ -53,21 +215,61 @@ impl OptionsList {
 
     fn up(&mut self) {
         self.list_state.select_previous();
+        self.skip_headings(false);
     }
 
     fn down(&mut self) {
         self.list_state.select_next();
+        self.skip_headings(true);
     }
 
+    /// Moves the selection off a heading to the next entry in the direction, or the other way
+    /// at the ends of the list
+    fn skip_headings(&mut self, down: bool) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        if !self
+            .contents
+            .get(selected)
+            .is_some_and(|entry| entry.heading)
+        {
+            return;
+        }
+
+        let below = (selected..self.contents.len()).find(|&idx| !self.contents[idx].heading);
+        let above = (0..selected).rev().find(|&idx| !self.contents[idx].heading);
+        let target = match down {
+            true => below.or(above),
+            false => above.or(below),
+        };
+        self.list_state.select(target.or(Some(selected)));
+    }
+
+    /// Position of the selected entry, headings are not counted
     pub fn current(&self) -> Option<usize> {
-        self.list_state.selected()
+        let selected = self.list_state.selected()?;
+        if self
+            .contents
+            .get(selected)
+            .is_some_and(|entry| entry.heading)
+        {
+            return None;
+        }
+        let headings = self
+            .contents
+            .iter()
+            .take(selected)
+            .filter(|entry| entry.heading)
+            .count();
+        Some(selected - headings)
     }
 
     pub fn current_value(&self) -> Option<&str> {
-        if let Some(idx) = self.list_state.selected() {
-            return Some(&self.contents[idx]);
-        }
-        None
+        self.list_state
+            .selected()
+            .and_then(|idx| self.contents.get(idx))
+            .map(|entry| entry.label.as_str())
     }
 
     fn clear_search_buffer(&mut self) {
@@ -83,11 +285,9 @@ impl OptionsList {
         self.last_time_buffer_written = Instant::now();
 
         // Find matches
-        if let Some(found_match) = self
-            .contents
-            .iter()
-            .position(|line| contains_ignore_ascii_case(line, &self.search_buffer))
-        {
+        if let Some(found_match) = self.contents.iter().position(|entry| {
+            !entry.heading && contains_ignore_ascii_case(&entry.label, &self.search_buffer)
+        }) {
             self.list_state.select(Some(found_match));
         }
     }
@@ -97,43 +297,63 @@ impl Widget for &mut OptionsList {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let config = CONFIG.read().unwrap();
 
-        let list_items = self.contents.iter().map(|line| {
-            let mut line = Cow::Borrowed(line.as_str());
+        let list_items = self.contents.iter().map(|entry| {
+            if entry.heading {
+                return ListItem::new(Line::from(entry.label.as_str()).bold().underlined());
+            }
+
+            let mut line = Cow::Borrowed(entry.label.as_str());
             if config.get_liked_animes().contains(line.as_ref()) {
                 line.to_mut().push_str(" ★");
             }
 
-            let Some(match_start) =
-                find_ignore_ascii_case(line.as_ref(), self.search_buffer.as_str())
-            else {
-                return ListItem::new(line);
+            let mut spans = match find_ignore_ascii_case(line.as_ref(), self.search_buffer.as_str())
+            {
+                Some(match_start) => {
+                    let match_end = match_start + self.search_buffer.len();
+                    vec![
+                        Span::raw(line[..match_start].to_owned()),
+                        Span::styled(
+                            line[match_start..match_end].to_owned(),
+                            Style::new()
+                                .fg(Color::Black)
+                                .bg(Color::Green)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(line[match_end..].to_owned()),
+                    ]
+                }
+                None => vec![Span::raw(line.into_owned())],
             };
-            let match_end = match_start + self.search_buffer.len();
-
-            ListItem::new(Line::from_iter([
-                Span::raw(line[..match_start].to_owned()),
-                Span::styled(
-                    line[match_start..match_end].to_owned(),
-                    Style::new()
-                        .fg(Color::Black)
-                        .bg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(line[match_end..].to_owned()),
-            ]))
+
+            for badge in &entry.badges {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(
+                    format!(" {badge} "),
+                    Style::new().fg(Color::Black).bg(Color::Cyan),
+                ));
+            }
+
+            ListItem::new(Line::from(spans))
         });
+        let load_more = self
+            .load_more
+            .then(|| ListItem::new(Line::from("Cargar más resultados...").fg(Color::DarkGray)));
 
-        let list = List::new(list_items)
+        let mut block = Block::new()
+            .borders(Borders::RIGHT)
+            .border_style(Style::new().fg(match self.focus {
+                true => Color::Yellow,
+                false => Color::White,
+            }));
+        if let Some(title) = &self.title {
+            block = block.title_top(Line::from(title.as_str()).right_aligned());
+        }
+
+        let list = List::new(list_items.chain(load_more))
             .highlight_symbol("> ")
             .highlight_style(Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-            .block(
-                Block::new()
-                    .borders(Borders::RIGHT)
-                    .border_style(Style::new().fg(match self.focus {
-                        true => Color::Yellow,
-                        false => Color::White,
-                    })),
-            );
+            .block(block);
 
         StatefulWidget::render(list, area, buf, &mut self.list_state);
     }
@@ -143,6 +363,8 @@ impl Default for OptionsList {
     fn default() -> Self {
         Self {
             contents: Default::default(),
+            title: Default::default(),
+            load_more: Default::default(),
             list_state: Default::default(),
             focus: Default::default(),
             search_buffer: Default::default(),
@@ -172,3 +394,40 @@ fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
         .windows(needle.len())
         .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
 }
+
+/// The schedule grouped by day, with a heading before the episodes of each one. The schedule is
+/// sorted by date.
+pub fn schedule_entries(schedule: &[ScheduledEpisode]) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    let mut day = None;
+    for scheduled in schedule {
+        if day != Some(scheduled.date) {
+            day = Some(scheduled.date);
+            entries.push(ListEntry::heading(day_label(scheduled.date)));
+        }
+        entries.push(scheduled.into());
+    }
+    entries
+}
+
+/// Day of the week relative to today in local time, e.g. "Hoy", "Mañana" or "Jueves 23/10"
+fn day_label(date: NaiveDate) -> String {
+    let today = Local::now().date_naive();
+    if date == today {
+        return "Hoy".to_owned();
+    }
+    if today.succ_opt() == Some(date) {
+        return "Mañana".to_owned();
+    }
+
+    let weekday = match date.weekday() {
+        Weekday::Mon => "Lunes",
+        Weekday::Tue => "Martes",
+        Weekday::Wed => "Miércoles",
+        Weekday::Thu => "Jueves",
+        Weekday::Fri => "Viernes",
+        Weekday::Sat => "Sábado",
+        Weekday::Sun => "Domingo",
+    };
+    format!("{} {}", weekday, date.format("%d/%m"))
+}
@@ -2,34 +2,55 @@ use anyhow::Result;
 use app::App;
 use config::ConfigApp;
 
+mod aggregate;
 mod app;
+mod check;
+mod details;
+mod filters;
 mod frontend;
+mod hls;
 mod input;
 mod list;
 mod logging;
+mod mirrors;
+mod page_data;
 mod server;
+mod worker;
 
 mod animeav1;
-mod animeav1sub;
 mod animeflv;
+mod browser;
 mod client;
+mod declarative;
+mod extractor;
+mod mp4upload;
+mod provider;
+mod script;
+mod streamtape;
+mod streamwish;
 
 mod config;
 
 fn main() -> Result<()> {
     color_eyre::install().expect("Could not install color eyre");
 
+    if std::env::args().any(|arg| arg == "--check-providers") {
+        config::Config::load();
+        let result = check::run();
+        browser::shutdown();
+        return result;
+    }
+
     // Config App
     let mut terminal = ratatui::init();
     let result = ConfigApp::default().run(&mut terminal);
     ratatui::restore();
-    if result.is_err() {
-        return result;
-    }
+    result?;
 
     // App
     let mut terminal = ratatui::init();
     let result = App::default().run(&mut terminal);
     ratatui::restore();
+    browser::shutdown();
     result
 }
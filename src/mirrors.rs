@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Result};
+use reqwest::blocking::{self, Response};
+
+/// Base urls of a provider, its site and the mirrors to use when it does not answer. The
+/// requests start on the last mirror that answered.
+pub struct Mirrors {
+    urls: Vec<String>,
+    current: AtomicUsize,
+}
+
+impl Mirrors {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls: urls
+                .iter()
+                .map(|url| url.trim_end_matches('/').to_owned())
+                .collect(),
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Scheme and host without the trailing slash, for the links of the pages
+    pub fn base(&self) -> &str {
+        self.urls
+            .get(self.current.load(Ordering::Relaxed))
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// GET of the path on the mirrors
+    pub fn get(&self, path: &str) -> Result<Response> {
+        self.fetch(|base| format!("{base}{path}"))
+    }
+
+    /// GET of the url built for each mirror, moving to the next one on connection errors
+    pub fn fetch(&self, url: impl Fn(&str) -> String) -> Result<Response> {
+        let start = self.current.load(Ordering::Relaxed);
+        let mut errors = Vec::new();
+
+        for offset in 0..self.urls.len() {
+            let idx = (start + offset) % self.urls.len();
+            match blocking::get(url(&self.urls[idx])) {
+                Ok(response) => {
+                    self.current.store(idx, Ordering::Relaxed);
+                    return Ok(response);
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    errors.push(format!("{}: {}", self.urls[idx], e))
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(anyhow!("Ningún espejo responde\n{}", errors.join("\n")))
+    }
+}
+
+/// Percent encoding of the search query
+pub fn encode(query: &str) -> String {
+    query
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b' ' => "+".to_owned(),
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use reqwest::blocking;
+
+use crate::extractor::{Container, Extractor, Stream};
+
+pub struct Mp4Upload;
+
+impl Extractor for Mp4Upload {
+    fn hosts(&self) -> &[&str] {
+        &["mp4upload.com"]
+    }
+
+    fn extract(&self, url: &str) -> Result<Stream> {
+        let response = blocking::get(url)?;
+        let text = response.text()?;
+
+        let pattern = r#"src: ""#;
+        let start_text_idx = text.find(pattern).ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Episode link not found (src: \")",
+        ))? + pattern.len();
+
+        let pattern = "\"";
+        let end_idx = text[start_text_idx..]
+            .find(pattern)
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Episode link end not found",
+            ))?
+            + start_text_idx;
+
+        Ok(Stream {
+            url: text[start_text_idx..end_idx].to_owned().replace("\\", ""),
+            // The video is only served to the player page
+            headers: vec![(
+                "Referer".to_owned(),
+                "https://www.mp4upload.com/".to_owned(),
+            )],
+            container: Container::Mp4,
+            audio: None,
+            probed: false,
+        })
+    }
+
+    fn needs_headers(&self) -> bool {
+        true
+    }
+}
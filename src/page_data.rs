@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::de::DeserializeOwned;
+
+lazy_static! {
+    /// Arguments of `eval(function(p,a,c,k,e,d){...}('payload',radix,count,'words'.split('|'),0,{}))`
+    static ref PACKED: Regex = Regex::new(
+        r#"\}\s*\(\s*'((?:[^'\\]|\\.)*)'\s*,\s*(\d+)\s*,\s*(\d+)\s*,\s*'((?:[^'\\]|\\.)*)'\.split\(\s*'\|'\s*\)"#
+    )
+    .unwrap();
+    static ref WORD: Regex = Regex::new(r"\b\w+\b").unwrap();
+}
+
+/// Json value of `var name = ...;` in the scripts of the page
+pub fn js_variable<T: DeserializeOwned>(text: &str, name: &str) -> Result<T> {
+    let pattern = format!("var {name} = ");
+    let start_idx = text
+        .find(&pattern)
+        .ok_or(anyhow!("Not found ({pattern})"))?
+        + pattern.len();
+
+    // Only the first value is read, the rest of the script is ignored
+    serde_json::Deserializer::from_str(&text[start_idx..])
+        .into_iter::<T>()
+        .next()
+        .ok_or(anyhow!("Empty value ({pattern})"))?
+        .map_err(|e| anyhow!("Unexpected shape of var {name}: {e}"))
+}
+
+/// Object literal of the `key:{...}` property of the data the SvelteKit pages embed, which is
+/// javascript rather than json (unquoted keys, `void 0`, ...)
+pub fn js_property<T: DeserializeOwned>(text: &str, key: &str) -> Result<T> {
+    let start_idx = [format!("{key}:{{"), format!("{key}:[")]
+        .iter()
+        .filter_map(|pattern| text.find(pattern).map(|idx| idx + key.len() + 1))
+        .min()
+        .ok_or(anyhow!("Not found ({key}:)"))?;
+
+    let json = js_to_json(&text[start_idx..]).map_err(|e| anyhow!("{key}: {e}"))?;
+    serde_json::from_str(&json).map_err(|e| anyhow!("Unexpected shape of {key}: {e}"))
+}
+
+/// Converts the object or array at the start of the text to json
+fn js_to_json(text: &str) -> Result<String> {
+    let mut json = String::new();
+    let mut depth = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((idx, char)) = chars.next() {
+        match char {
+            '{' | '[' => {
+                depth += 1;
+                json.push(char);
+            }
+            '}' | ']' => {
+                depth -= 1;
+                json.push(char);
+                if depth == 0 {
+                    return Ok(json);
+                }
+            }
+            '"' | '\'' => {
+                json.push('"');
+                let mut escaped = false;
+                for (_, inner) in chars.by_ref() {
+                    match inner {
+                        _ if escaped => {
+                            escaped = false;
+                            // \' is not a valid json escape
+                            if inner == '\'' {
+                                json.pop();
+                            }
+                            json.push(inner);
+                        }
+                        '\\' => {
+                            escaped = true;
+                            json.push(inner);
+                        }
+                        _ if inner == char => break,
+                        '"' => json.push_str("\\\""),
+                        _ => json.push(inner),
+                    }
+                }
+                json.push('"');
+            }
+            '!' => match chars.next() {
+                Some((_, '0')) => json.push_str("true"),
+                Some((_, '1')) => json.push_str("false"),
+                _ => return Err(anyhow!("Unexpected value at {idx}")),
+            },
+            char if char.is_alphabetic() || char == '_' || char == '$' => {
+                let mut end = idx + char.len_utf8();
+                while let Some((next_idx, next)) = chars.peek() {
+                    if !(next.is_alphanumeric() || *next == '_' || *next == '$') {
+                        break;
+                    }
+                    end = next_idx + next.len_utf8();
+                    chars.next();
+                }
+                let identifier = &text[idx..end];
+
+                if text[end..].trim_start().starts_with(':') {
+                    json.push_str(&format!("\"{identifier}\""));
+                    continue;
+                }
+                match identifier {
+                    "true" | "false" | "null" => json.push_str(identifier),
+                    "undefined" => json.push_str("null"),
+                    "void" => {
+                        // void 0
+                        while chars.next_if(|(_, next)| next.is_whitespace()).is_some() {}
+                        chars.next_if(|(_, next)| next.is_ascii_digit());
+                        json.push_str("null");
+                    }
+                    _ => return Err(anyhow!("Unsupported value {identifier}")),
+                }
+            }
+            _ => json.push(char),
+        }
+    }
+
+    Err(anyhow!("Unexpected end of the data"))
+}
+
+/// Source of the first script of the page packed with `eval(function(p,a,c,k,e,d)...)`, which
+/// video hosts use to hide the player setup
+pub fn unpack(text: &str) -> Result<String> {
+    let start_idx = text
+        .find("eval(function(p,a,c,k,e,d)")
+        .ok_or(anyhow!("Not found (eval(function(p,a,c,k,e,d))"))?;
+    let captures = PACKED
+        .captures(&text[start_idx..])
+        .ok_or(anyhow!("Unexpected arguments of the packed script"))?;
+
+    let payload = unescape(&captures[1]);
+    let radix: u32 = captures[2].parse()?;
+    let count: usize = captures[3].parse()?;
+    let words: Vec<&str> = captures[4].split('|').collect();
+    if !(2..=62).contains(&radix) {
+        return Err(anyhow!("Unsupported radix {radix} of the packed script"));
+    }
+
+    let unpacked = WORD.replace_all(&payload, |word: &Captures| {
+        let word = &word[0];
+        match decode(word, radix) {
+            Some(idx) if idx < count => match words.get(idx) {
+                Some(replacement) if !replacement.is_empty() => replacement.to_string(),
+                _ => word.to_owned(),
+            },
+            _ => word.to_owned(),
+        }
+    });
+    Ok(unpacked.into_owned())
+}
+
+/// Number written with the digits of the packer, `0-9a-zA-Z`
+fn decode(word: &str, radix: u32) -> Option<usize> {
+    word.chars().try_fold(0usize, |number, char| {
+        let digit = match char {
+            '0'..='9' => char as u32 - '0' as u32,
+            'a'..='z' => char as u32 - 'a' as u32 + 10,
+            'A'..='Z' => char as u32 - 'A' as u32 + 36,
+            _ => return None,
+        };
+        if digit >= radix {
+            return None;
+        }
+        number
+            .checked_mul(radix as usize)?
+            .checked_add(digit as usize)
+    })
+}
+
+/// Contents of a single quoted javascript string
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => result.extend(chars.next()),
+            _ => result.push(char),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn js_variable_reads_past_semicolons_in_strings() {
+        let text = r#"<script>var videos = {"SUB":[{"server":"sw","code":"https:\/\/a.b\/e?x=1;y=2"}]}; var other = 1;</script>"#;
+        let videos: Value = js_variable(text, "videos").unwrap();
+        assert_eq!(videos["SUB"][0]["code"], "https://a.b/e?x=1;y=2");
+    }
+
+    #[test]
+    fn js_variable_reports_the_shape() {
+        let text = "var episodes = [[1,2]];";
+        let error = js_variable::<Vec<String>>(text, "episodes").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Unexpected shape of var episodes"));
+        assert!(js_variable::<Value>(text, "missing").is_err());
+    }
+
+    #[test]
+    fn js_property_converts_object_literals() {
+        let text = r#"data:[{type:"data",data:{embeds:{SUB:[{server:'MP4Upload',url:'https://a.b/it\'s;1'}],DUB:[{server:"HLS",url:"x",extra:void 0,hd:!0,old:!1,title:'say "hi"',none:undefined}]}}}]"#;
+        let embeds: Value = js_property(text, "embeds").unwrap();
+        assert_eq!(
+            embeds,
+            json!({
+                "SUB": [{ "server": "MP4Upload", "url": "https://a.b/it's;1" }],
+                "DUB": [{
+                    "server": "HLS",
+                    "url": "x",
+                    "extra": null,
+                    "hd": true,
+                    "old": false,
+                    "title": "say \"hi\"",
+                    "none": null
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn js_property_needs_the_whole_value() {
+        assert!(js_property::<Value>("embeds:{SUB:[", "embeds").is_err());
+        assert!(js_property::<Value>("embeds:{SUB:foo}", "embeds").is_err());
+    }
+
+    /// `eval(function(p,a,c,k,e,d){...}(...))` with the given payload, radix and words
+    fn packed(payload: &str, radix: u32, words: &[&str]) -> String {
+        format!(
+            "<script>eval(function(p,a,c,k,e,d){{while(c--)if(k[c])p=p.replace(new RegExp('\\\\b'+c.toString(a)+'\\\\b','g'),k[c]);return p}}('{payload}',{radix},{},'{}'.split('|'),0,{{}}))</script>",
+            words.len(),
+            words.join("|")
+        )
+    }
+
+    #[test]
+    fn unpack_radix_62() {
+        let mut words = vec![""; 63];
+        words[10] = "jwplayer";
+        words[36] = "file";
+        words[62] = "m3u8";
+        let text = packed(r"a({A:\'x.10\'})", 62, &words);
+
+        // "x" has no word and stays as it is
+        assert_eq!(unpack(&text).unwrap(), "jwplayer({file:'x.m3u8'})");
+    }
+
+    #[test]
+    fn unpack_keeps_words_out_of_the_radix() {
+        let text = packed("0.A_1", 36, &["sources", "file"]);
+        assert_eq!(unpack(&text).unwrap(), "sources.A_1");
+    }
+
+    #[test]
+    fn unpack_without_packed_script() {
+        assert!(unpack("<script>var a = 1;</script>").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+use crate::{
+    animeav1, animeflv, client::Client, config::CONFIG, declarative, mirrors::Mirrors, script,
+};
+
+pub const DEFAULT_PROVIDER: &str = animeav1::ID;
+
+lazy_static! {
+    pub static ref PROVIDERS: Registry = Registry::new();
+}
+
+pub struct Provider {
+    pub id: String,
+    pub name: String,
+    /// Audio languages the provider can serve, e.g. "SUB" or "DUB"
+    pub languages: Vec<String>,
+    /// Headers the video hosts of the provider need to serve the stream
+    pub headers: Vec<(String, String)>,
+    pub client: Arc<dyn Client>,
+}
+
+impl Provider {
+    /// Falls back to the first language of the provider when it does not have the preferred one
+    pub fn language<'a>(&'a self, preferred: &'a str) -> &'a str {
+        if self.languages.iter().any(|language| language == preferred) {
+            return preferred;
+        }
+        self.languages
+            .first()
+            .map(String::as_str)
+            .unwrap_or(preferred)
+    }
+}
+
+pub struct Registry {
+    providers: Vec<Provider>,
+    /// Provider definitions that could not be loaded
+    errors: Vec<String>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Self::with_base_url(None)
+    }
+
+    /// Points every provider to `base_url` instead of its site and mirrors, e.g. a local server
+    /// to check the scrapers against. Scripted providers build their own urls and are not
+    /// affected.
+    pub fn with_base_url(base_url: Option<&str>) -> Self {
+        let mut registry = Self {
+            errors: Vec::new(),
+            providers: vec![
+                Provider {
+                    id: animeflv::ID.to_owned(),
+                    name: "AnimeFlv".to_owned(),
+                    languages: vec!["SUB".to_owned()],
+                    headers: Vec::new(),
+                    client: Arc::new(animeflv::AnimeFlv::new(mirrors(
+                        animeflv::ID,
+                        base_url,
+                        &[animeflv::BASE_URL],
+                    ))),
+                },
+                Provider {
+                    id: animeav1::ID.to_owned(),
+                    name: "AnimeAv1".to_owned(),
+                    languages: animeav1::LANGUAGES.map(str::to_owned).to_vec(),
+                    headers: Vec::new(),
+                    client: Arc::new(animeav1::AnimeAv1::new(mirrors(
+                        animeav1::ID,
+                        base_url,
+                        &[animeav1::BASE_URL],
+                    ))),
+                },
+            ],
+        };
+
+        let (mut providers, errors) = declarative::load(base_url);
+        registry.errors = errors;
+        let (scripts, errors) = script::load();
+        providers.extend(scripts);
+        registry.errors.extend(errors);
+        for provider in providers {
+            if registry.position(&provider.id).is_some() {
+                registry.errors.push(format!(
+                    "Ya existe un proveedor con el id \"{}\"",
+                    provider.id
+                ));
+                continue;
+            }
+            registry.providers.push(provider);
+        }
+
+        registry
+    }
+
+    pub fn all(&self) -> &[Provider] {
+        &self.providers
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Unknown ids (e.g. a provider removed since the config was saved) get the default provider
+    pub fn get(&self, id: &str) -> &Provider {
+        self.position(id)
+            .or_else(|| self.position(DEFAULT_PROVIDER))
+            .map(|idx| &self.providers[idx])
+            .unwrap_or(&self.providers[0])
+    }
+
+    pub fn client(&self, id: &str) -> Arc<dyn Client> {
+        self.get(id).client.clone()
+    }
+
+    pub fn next(&self, id: &str) -> &Provider {
+        let idx = self.position(id).map(|idx| idx + 1).unwrap_or(0);
+        &self.providers[idx % self.providers.len()]
+    }
+
+    pub fn previous(&self, id: &str) -> &Provider {
+        let idx = self.position(id).unwrap_or(0);
+        &self.providers[(idx + self.providers.len() - 1) % self.providers.len()]
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.providers.iter().position(|provider| provider.id == id)
+    }
+}
+
+/// Base urls of a provider: the override, the ones set in the config or its own ones
+pub fn mirrors(id: &str, base_url: Option<&str>, defaults: &[&str]) -> Mirrors {
+    if let Some(base_url) = base_url {
+        return Mirrors::new(vec![base_url.to_owned()]);
+    }
+
+    let config = CONFIG.read().unwrap();
+    match config.get_base_urls(id) {
+        [] => Mirrors::new(defaults.iter().map(|url| url.to_string()).collect()),
+        urls => Mirrors::new(urls.to_vec()),
+    }
+}
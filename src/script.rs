@@ -0,0 +1,397 @@
+use std::{collections::BTreeMap, fs, path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
+use reqwest::blocking;
+use rhai::{
+    serde::{from_dynamic, to_dynamic},
+    Array, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST,
+};
+use scraper::{Html, Selector};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    client::{
+        AnimeSummary, Client, Episode, Filters, LatestEpisode, ScheduledEpisode, SearchPage,
+        SeriesDetails, Source, SourceKind,
+    },
+    extractor::Stream,
+    page_data,
+    provider::Provider,
+};
+
+/// Operations a call to the script can run, the work of the registered functions not included
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Returned by `info()`
+#[derive(Deserialize)]
+struct ScriptInfo {
+    id: String,
+    name: String,
+    #[serde(default)]
+    languages: Vec<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+/// Returned by `search(query, page)` and `browse(filters, page)`
+#[derive(Deserialize)]
+struct ScriptPage {
+    animes: Vec<ScriptAnime>,
+    #[serde(default)]
+    has_next: bool,
+}
+
+#[derive(Deserialize)]
+struct ScriptAnime {
+    slug: String,
+    title: String,
+    cover: Option<String>,
+    synopsis: Option<String>,
+    #[serde(default)]
+    genres: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ScriptLatest {
+    slug: String,
+    title: String,
+    number: String,
+}
+
+/// Returned by `schedule()`, with the air time (e.g. "2025-10-20T18:30:00+09:00") or only the
+/// day ("2025-10-20")
+#[derive(Deserialize)]
+struct ScriptScheduled {
+    slug: String,
+    title: String,
+    date: Option<String>,
+    time: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScriptEpisode {
+    id: String,
+    number: Option<String>,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScriptSource {
+    server: String,
+    url: String,
+    language: Option<String>,
+    #[serde(default)]
+    download: bool,
+}
+
+/// Provider implemented by a rhai script of the providers dir. The script defines `info()`,
+/// `search(query, page)`, `series(id)`, `episodes(series_id)` and `sources(series_id,
+/// episode_id)`; `browse(filters, page)`, `latest()`, `schedule()` and `resolve(url)` are
+/// optional.
+pub struct Script {
+    id: String,
+    languages: Vec<String>,
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    fn call<T: DeserializeOwned>(&self, name: &str, args: impl FuncArgs) -> Result<T> {
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args)
+            .map_err(|e| anyhow!("{name}: {e}"))?;
+        from_dynamic(&result).map_err(|e| anyhow!("{name}: {e}"))
+    }
+
+    fn has_fn(&self, name: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name)
+    }
+
+    fn page(&self, page: ScriptPage, number: u32) -> SearchPage {
+        SearchPage {
+            animes: page
+                .animes
+                .into_iter()
+                .map(|anime| {
+                    let mut summary = AnimeSummary::new(&self.id, &anime.slug, &anime.title);
+                    summary.cover = anime.cover;
+                    summary
+                })
+                .collect(),
+            page: number,
+            has_next: page.has_next,
+        }
+    }
+}
+
+impl Client for Script {
+    fn get_animes(&self, query: &str, page: u32) -> Result<SearchPage> {
+        let result = self.call("search", (query.to_owned(), page as i64))?;
+        Ok(self.page(result, page))
+    }
+
+    fn browse(&self, filters: &Filters, page: u32) -> Result<SearchPage> {
+        if !self.has_fn("browse") {
+            return Err(anyhow!("El catálogo de {} no se puede explorar", self.id));
+        }
+
+        let mut map = Map::new();
+        map.insert("genre".into(), to_dynamic(&filters.genre)?);
+        map.insert("year".into(), to_dynamic(filters.year)?);
+        let result = self.call("browse", (map, page as i64))?;
+        Ok(self.page(result, page))
+    }
+
+    fn latest(&self) -> Result<Vec<LatestEpisode>> {
+        if !self.has_fn("latest") {
+            return Ok(Vec::new());
+        }
+
+        let latest: Vec<ScriptLatest> = self.call("latest", ())?;
+        Ok(latest
+            .into_iter()
+            .enumerate()
+            .map(|(position, latest)| LatestEpisode {
+                series: AnimeSummary::new(&self.id, &latest.slug, &latest.title),
+                episode: Episode::new(&latest.number, &latest.number, position),
+            })
+            .collect())
+    }
+
+    fn schedule(&self) -> Result<Vec<ScheduledEpisode>> {
+        if !self.has_fn("schedule") {
+            return Ok(Vec::new());
+        }
+
+        let schedule: Vec<ScriptScheduled> = self.call("schedule", ())?;
+        schedule
+            .into_iter()
+            .map(|scheduled| {
+                let time = scheduled
+                    .time
+                    .map(|time| DateTime::parse_from_rfc3339(&time))
+                    .transpose()
+                    .map_err(|e| anyhow!("schedule: {e}"))?
+                    .map(|time| time.with_timezone(&Local));
+                let date = match (time, scheduled.date) {
+                    (Some(time), _) => time.date_naive(),
+                    (None, Some(date)) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                        .map_err(|e| anyhow!("schedule: {e}"))?,
+                    (None, None) => {
+                        return Err(anyhow!("schedule: {} has no date", scheduled.title))
+                    }
+                };
+                Ok(ScheduledEpisode {
+                    series: AnimeSummary::new(&self.id, &scheduled.slug, &scheduled.title),
+                    date,
+                    time,
+                })
+            })
+            .collect()
+    }
+
+    fn series(&self, id: &str) -> Result<SeriesDetails> {
+        let anime: ScriptAnime = self.call("series", (id.to_owned(),))?;
+
+        let mut summary = AnimeSummary::new(&self.id, id, &anime.title);
+        summary.cover = anime.cover;
+        let mut details = SeriesDetails::new(summary);
+        details.synopsis = anime.synopsis;
+        details.genres = anime.genres;
+        Ok(details)
+    }
+
+    fn episodes(&self, series_id: &str) -> Result<Vec<Episode>> {
+        let episodes: Vec<ScriptEpisode> = self.call("episodes", (series_id.to_owned(),))?;
+        Ok(episodes
+            .into_iter()
+            .enumerate()
+            .map(|(position, script)| {
+                let number = script.number.as_deref().unwrap_or(&script.id);
+                let mut episode = Episode::new(&script.id, number, position);
+                episode.title = script.title;
+                episode
+            })
+            .collect())
+    }
+
+    fn sources(&self, series_id: &str, episode: &Episode) -> Result<Vec<Source>> {
+        let sources: Vec<ScriptSource> =
+            self.call("sources", (series_id.to_owned(), episode.id.clone()))?;
+        let language = self
+            .languages
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "SUB".to_owned());
+
+        Ok(sources
+            .into_iter()
+            .map(|source| Source {
+                server: source.server,
+                language: source.language.unwrap_or(language.clone()),
+                kind: match source.download {
+                    true => SourceKind::Download,
+                    false => SourceKind::Embed,
+                },
+                url: source.url,
+            })
+            .collect())
+    }
+
+    /// `resolve(url)` can turn the link into another one, which still goes through the
+    /// extractors
+    fn resolve(&self, source: &Source) -> Result<Stream> {
+        let url = match self.has_fn("resolve") {
+            true => self.call("resolve", (source.url.clone(),))?,
+            false => source.url.clone(),
+        };
+        let source = Source {
+            url,
+            ..source.clone()
+        };
+        source.stream()
+    }
+}
+
+/// Compiles every `.rhai` script of the providers dir, the second value has the scripts that
+/// could not be loaded
+pub fn load() -> (Vec<Provider>, Vec<String>) {
+    let dirs =
+        directories::ProjectDirs::from("", "", "ani-cli-es").expect("Could not get the config dir");
+    let Ok(entries) = fs::read_dir(dirs.config_dir().join("providers")) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "rhai")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut providers = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        match load_script(&path) {
+            Ok(provider) => providers.push(provider),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    (providers, errors)
+}
+
+fn load_script(path: &Path) -> Result<Provider> {
+    let engine = engine();
+    let ast = engine
+        .compile_file(path.to_owned())
+        .map_err(|e| anyhow!("{e}"))?;
+
+    let mut script = Script {
+        id: String::new(),
+        languages: Vec::new(),
+        engine,
+        ast,
+    };
+    let mut info: ScriptInfo = script.call("info", ())?;
+    if info.languages.is_empty() {
+        info.languages.push("SUB".to_owned());
+    }
+    script.id = info.id.clone();
+    script.languages = info.languages.clone();
+
+    Ok(Provider {
+        id: info.id,
+        name: info.name,
+        languages: info.languages,
+        headers: info.headers.into_iter().collect(),
+        client: Arc::new(script),
+    })
+}
+
+/// Engine with the http and html helpers available to the scripts
+/// A script stuck in a loop stops with an error instead of using the cpu forever
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 32)
+        // Whole pages are read into strings
+        .set_max_string_size(16 * 1024 * 1024)
+        .set_max_array_size(100_000)
+        .set_max_map_size(10_000);
+    engine
+        .register_fn("http_get", http_get)
+        .register_fn("html_select", html_select)
+        .register_fn("html_text", html_text)
+        .register_fn("html_attr", html_attr)
+        .register_fn("js_variable", js_variable)
+        .register_fn("regex_captures", regex_captures)
+        .register_fn("parse_json", parse_json);
+    engine
+}
+
+fn http_get(url: &str) -> Result<String, Box<EvalAltResult>> {
+    blocking::get(url)
+        .and_then(|response| response.text())
+        .map_err(|e| e.to_string().into())
+}
+
+/// Outer html of every element matching the selector
+fn html_select(html: &str, selector: &str) -> Result<Array, Box<EvalAltResult>> {
+    let selector = Selector::parse(selector).map_err(|e| e.to_string())?;
+    Ok(Html::parse_document(html)
+        .select(&selector)
+        .map(|element| element.html().into())
+        .collect())
+}
+
+fn html_text(html: &str) -> String {
+    Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Attribute of the first element of the html, empty when it does not have it
+fn html_attr(html: &str, name: &str) -> String {
+    Html::parse_fragment(html)
+        .root_element()
+        .child_elements()
+        .next()
+        .and_then(|element| element.attr(name))
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Value of `var name = ...;` as json, empty when the page does not have it
+fn js_variable(text: &str, name: &str) -> String {
+    page_data::js_variable::<serde_json::Value>(text, name)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+/// Every match of the pattern, the first capture group when there is one
+fn regex_captures(text: &str, pattern: &str) -> Result<Array, Box<EvalAltResult>> {
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+    Ok(regex
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1).or(captures.get(0)))
+        .map(|value| value.as_str().into())
+        .collect())
+}
+
+fn parse_json(text: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    to_dynamic(value)
+}
@@ -1,29 +1,33 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::prelude::*;
-use serde::{Deserialize, Serialize};
 
-use crate::{animeav1, animeav1sub, animeflv, client::Client, config::CONFIG};
+use crate::{config::CONFIG, provider::PROVIDERS};
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
-pub enum Server {
-    AnimeFlv,
-    AnimeAv1,
-    AnimeAv1SUB,
+pub struct Servers {
+    current_server: String,
+    language: String,
+    /// Provider and embed groups of the open series that the provider does not list
+    series_languages: (String, Vec<String>),
+
+    focus: bool,
 }
 
-impl Default for Server {
+impl Default for Servers {
     fn default() -> Self {
-        CONFIG.read().unwrap().get_server()
+        let config = CONFIG.read().unwrap();
+        let server = config.get_server().to_owned();
+        let language = config.get_language().to_owned();
+        // The registry reads the config when it is first used
+        drop(config);
+        Self {
+            current_server: PROVIDERS.get(&server).id.clone(),
+            language,
+            series_languages: (String::new(), Vec::new()),
+            focus: false,
+        }
     }
 }
 
-#[derive(Default)]
-pub struct Servers {
-    current_server: Server,
-
-    focus: bool,
-}
-
 impl Servers {
     pub fn focus(&mut self) {
         self.focus = true;
@@ -33,48 +37,79 @@ impl Servers {
         self.focus = false;
     }
 
-    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<Box<dyn Client>> {
+    pub fn current(&self) -> &str {
+        &self.current_server
+    }
+
+    /// Language to request to the current provider
+    pub fn language(&self) -> &str {
+        if self.languages().contains(&self.language.as_str()) {
+            return &self.language;
+        }
+        PROVIDERS.get(&self.current_server).language(&self.language)
+    }
+
+    /// Offers the languages of the episodes of the open series in the toggle of its provider
+    pub fn set_series_languages(&mut self, provider: &str, languages: Vec<String>) {
+        self.series_languages = (provider.to_owned(), languages);
+    }
+
+    fn languages(&self) -> Vec<&str> {
+        let mut languages: Vec<&str> = PROVIDERS
+            .get(&self.current_server)
+            .languages
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let (provider, series_languages) = &self.series_languages;
+        if *provider == self.current_server {
+            for language in series_languages {
+                if !languages.contains(&language.as_str()) {
+                    languages.push(language);
+                }
+            }
+        }
+        languages
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) {
         match key_event.code {
-            KeyCode::Right => Some(self.right()),
-            KeyCode::Left => Some(self.left()),
-            _ => None,
+            KeyCode::Right => self.right(),
+            KeyCode::Left => self.left(),
+            KeyCode::Up | KeyCode::Down => self.toggle_language(),
+            _ => (),
         }
     }
 
-    fn right(&mut self) -> Box<dyn Client> {
-        self.current_server = match self.current_server {
-            Server::AnimeFlv => Server::AnimeAv1SUB,
-            Server::AnimeAv1SUB => Server::AnimeAv1,
-            Server::AnimeAv1 => Server::AnimeFlv,
+    fn toggle_language(&mut self) {
+        let languages = self.languages();
+        let Some(idx) = languages
+            .iter()
+            .position(|language| *language == self.language())
+        else {
+            return;
         };
+        self.language = languages[(idx + 1) % languages.len()].to_owned();
+
+        CONFIG.write().unwrap().set_language(self.language.clone());
+    }
+
+    fn right(&mut self) {
+        self.current_server = PROVIDERS.next(&self.current_server).id.clone();
 
         CONFIG
             .write()
             .unwrap()
             .set_client(self.current_server.clone());
-        Servers::generate_current_client(&self.current_server)
     }
 
-    fn left(&mut self) -> Box<dyn Client> {
-        self.current_server = match self.current_server {
-            Server::AnimeAv1SUB => Server::AnimeFlv,
-            Server::AnimeAv1 => Server::AnimeAv1SUB,
-            Server::AnimeFlv => Server::AnimeAv1,
-        };
+    fn left(&mut self) {
+        self.current_server = PROVIDERS.previous(&self.current_server).id.clone();
 
         CONFIG
             .write()
             .unwrap()
             .set_client(self.current_server.clone());
-        Servers::generate_current_client(&self.current_server)
-    }
-
-    pub fn generate_current_client(server: &Server) -> Box<dyn Client> {
-        match server {
-            Server::AnimeFlv => Box::new(animeflv::AnimeFlv::default()),
-            Server::AnimeAv1 => Box::new(animeav1::AnimeAv1::default()),
-            Server::AnimeAv1SUB => Box::new(animeav1sub::AnimeAv1SUB::default()),
-        }
     }
 }
 
@@ -86,31 +121,34 @@ impl Widget for &Servers {
             Color::White
         };
 
-        let spans = vec![
-            Span::raw("AnimeFlv").fg(fg_color).add_modifier(
-                if self.current_server == Server::AnimeFlv {
+        let mut spans = Vec::new();
+        for provider in PROVIDERS.all() {
+            if !spans.is_empty() {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::raw(provider.name.as_str()).fg(fg_color).add_modifier(
+                if self.current_server == provider.id {
                     Modifier::UNDERLINED
                 } else {
                     Modifier::empty()
                 },
-            ),
-            Span::raw("  "),
-            Span::raw("AnimeAv1 (SUB)").fg(fg_color).add_modifier(
-                if self.current_server == Server::AnimeAv1SUB {
-                    Modifier::UNDERLINED
-                } else {
-                    Modifier::empty()
-                },
-            ),
-            Span::raw("  "),
-            Span::raw("AnimeAv1").fg(fg_color).add_modifier(
-                if self.current_server == Server::AnimeAv1 {
-                    Modifier::UNDERLINED
+            ));
+
+            let languages = self.languages();
+            if self.current_server != provider.id || languages.len() < 2 {
+                continue;
+            }
+            // Language toggle of the selected provider
+            spans.push(Span::raw(" "));
+            for language in languages {
+                let span = Span::raw(format!("[{language}]"));
+                spans.push(if language == self.language() {
+                    span.fg(Color::Black).bg(fg_color)
                 } else {
-                    Modifier::empty()
-                },
-            ),
-        ];
+                    span.fg(Color::DarkGray)
+                });
+            }
+        }
 
         Line::from(spans).right_aligned().render(area, buf);
     }
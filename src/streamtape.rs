@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::{blocking, Url};
+
+use crate::{
+    browser,
+    extractor::{Container, Extractor, Stream},
+};
+
+/// Storage the videos are served from, the browser waits for a request to it
+const VIDEO_PATTERN: &str = "radosgw";
+
+lazy_static! {
+    /// `document.getElementById('robotlink').innerHTML = '//streamtape.com/get_video?id=..&token=' + ('xcdtoken').substring(1).substring(2);`
+    static ref LINK: Regex = Regex::new(
+        r#"getElementById\(\s*['"](?:robot|ideoo|norobot)link['"]\s*\)\.innerHTML\s*=\s*['"]([^'"]+)['"]\s*\+\s*\(?\s*['"]([^'"]+)['"]\s*\)?((?:\.substring\(\s*\d+\s*\))*)"#
+    )
+    .unwrap();
+    static ref SUBSTRING: Regex = Regex::new(r"\.substring\(\s*(\d+)\s*\)").unwrap();
+}
+
+pub struct Streamtape;
+
+impl Extractor for Streamtape {
+    fn hosts(&self) -> &[&str] {
+        &[
+            "streamtape.com",
+            "streamtape.net",
+            "streamtape.to",
+            "streamtape.xyz",
+            "strtape.cloud",
+            "stape.fun",
+        ]
+    }
+
+    /// Rebuilds the video url from the scripts of the page, running them in a browser only
+    /// when the page changed
+    fn extract(&self, url: &str) -> Result<Stream> {
+        let native_error = match extract_native(url) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => e,
+        };
+        sniff(url).map_err(|e| anyhow!("{native_error}\nNavegador: {e}"))
+    }
+}
+
+fn extract_native(url: &str) -> Result<Stream> {
+    let text = blocking::get(url)?.error_for_status()?.text()?;
+    let mut stream = Stream {
+        url: video_url(&text)?,
+        headers: Vec::new(),
+        container: Container::Mp4,
+        audio: None,
+        probed: false,
+    };
+    // A token rebuilt from a page that changed gives a link that does not answer
+    stream.probe()?;
+    stream.probed = true;
+    Ok(stream)
+}
+
+/// The page writes decoy links before the real one, the last assignment is the one the player
+/// uses
+fn video_url(text: &str) -> Result<String> {
+    let captures = LINK.captures_iter(text).last().ok_or(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "Episode link not found (getElementById('robotlink').innerHTML)",
+    ))?;
+
+    let mut token = captures[2].to_owned();
+    for substring in SUBSTRING.captures_iter(&captures[3]) {
+        let start: usize = substring[1].parse()?;
+        token = token.get(start..).unwrap_or_default().to_owned();
+    }
+
+    let link = format!("{}{}", &captures[1], token);
+    // The page writes the link without the scheme, with one or two slashes
+    let link = match link.starts_with("http") {
+        true => link,
+        false => format!("https://{}", link.trim_start_matches('/')),
+    };
+    // Without stream=1 the link downloads the video instead of streaming it
+    let link = Url::parse(&format!("{link}&stream=1"))
+        .map_err(|e| anyhow!("Unexpected episode link {link}: {e}"))?;
+    if link.host_str().is_none_or(|host| !host.contains('.')) {
+        return Err(anyhow!("Unexpected episode link {link}"));
+    }
+    Ok(link.to_string())
+}
+
+/// Last resort, the video url is read from the requests of a browser that runs the page
+fn sniff(url: &str) -> Result<Stream> {
+    let url = browser::sniff(url, VIDEO_PATTERN, Duration::from_secs(10))
+        .map_err(|e| anyhow!("Could not scrape the episode url from stape: {e}"))?;
+    Ok(Stream {
+        url,
+        headers: Vec::new(),
+        container: Container::Mp4,
+        audio: None,
+        probed: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_the_last_link_of_the_page() {
+        let page = r#"
+            document.getElementById('ideoolink').innerHTML = "/streamtape.com/get_video?id=1&token=" + ('xyzdecoy');
+            document.getElementById('robotlink').innerHTML = '//streamtape.com/get_video?id=1&token=' + ('xcdtoken').substring(1).substring(2);
+        "#;
+        assert_eq!(
+            video_url(page).unwrap(),
+            "https://streamtape.com/get_video?id=1&token=token&stream=1"
+        );
+    }
+
+    #[test]
+    fn adds_the_scheme_to_the_link() {
+        let one_slash = "getElementById('norobotlink').innerHTML = '/stape.fun/get_video?id=2&token=' + ('abc');";
+        assert_eq!(
+            video_url(one_slash).unwrap(),
+            "https://stape.fun/get_video?id=2&token=abc&stream=1"
+        );
+
+        let absolute = "getElementById('robotlink').innerHTML = 'https://streamtape.to/get_video?id=3&token=' + ('-abc').substring(1);";
+        assert_eq!(
+            video_url(absolute).unwrap(),
+            "https://streamtape.to/get_video?id=3&token=abc&stream=1"
+        );
+    }
+
+    #[test]
+    fn rejects_links_without_host() {
+        let page = "getElementById('robotlink').innerHTML = '/get_video?id=4&token=' + ('abc');";
+        assert!(video_url(page).is_err());
+        assert!(video_url("<html>no player</html>").is_err());
+    }
+}
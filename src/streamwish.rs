@@ -0,0 +1,72 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::{blocking, Url};
+
+use crate::{
+    extractor::{Container, Extractor, Stream},
+    page_data,
+};
+
+lazy_static! {
+    /// `sources:[{file:"https://.../master.m3u8?t=..."}]`
+    static ref FILE: Regex = Regex::new(r#"file\s*:\s*["']([^"']+\.m3u8[^"']*)["']"#).unwrap();
+    /// `var links={"hls4":"/stream/...","hls2":"https://..."}`, newer players pick one of them
+    static ref LINKS: Regex = Regex::new(r#"["']hls\d["']\s*:\s*["']([^"']+\.m3u8[^"']*)["']"#).unwrap();
+}
+
+pub struct StreamWish;
+
+impl Extractor for StreamWish {
+    fn hosts(&self) -> &[&str] {
+        &[
+            "streamwish.to",
+            "streamwish.com",
+            "streamwish.site",
+            "strwish.com",
+            "embedwish.com",
+            "wishembed.pro",
+            "sfastwish.com",
+            "awish.pro",
+            "dwish.pro",
+            "playerwish.com",
+            "hlswish.com",
+            "swdyu.com",
+        ]
+    }
+
+    /// The player setup with the playlist is packed in a script of the embed page
+    fn extract(&self, url: &str) -> Result<Stream> {
+        let page_url = Url::parse(url)?;
+        let text = blocking::get(url)?.error_for_status()?.text()?;
+        // Some mirrors serve the player unpacked
+        let script = page_data::unpack(&text).unwrap_or(text);
+
+        let playlist = FILE
+            .captures(&script)
+            .or_else(|| LINKS.captures(&script))
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Episode link not found (file:\"...m3u8\")",
+            ))?[1]
+            .to_owned();
+
+        let origin = page_url.origin().ascii_serialization();
+        Ok(Stream {
+            // The player also uses links relative to the host
+            url: page_url.join(&playlist)?.to_string(),
+            // The playlist is only served to the player page
+            headers: vec![
+                ("Referer".to_owned(), format!("{origin}/")),
+                ("Origin".to_owned(), origin),
+            ],
+            container: Container::Hls,
+            audio: None,
+            probed: false,
+        })
+    }
+
+    fn needs_headers(&self) -> bool {
+        true
+    }
+}
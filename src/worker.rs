@@ -0,0 +1,156 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+
+use crate::{
+    aggregate::AggregatedAnime,
+    client::{
+        AnimeSummary, Episode, LatestEpisode, ResolvedEpisode, ScheduledEpisode, SearchPage,
+        SeriesDetails, Source,
+    },
+    hls::Variant,
+};
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+pub enum Response {
+    Latest {
+        latest: Vec<LatestEpisode>,
+        errors: Vec<String>,
+    },
+    Schedule {
+        schedule: Vec<ScheduledEpisode>,
+        errors: Vec<String>,
+    },
+    Animes(SearchPage),
+    Aggregated {
+        animes: Vec<AggregatedAnime>,
+        errors: Vec<String>,
+    },
+    Episodes {
+        series: AnimeSummary,
+        /// Missing when the page of the series could not be parsed
+        details: Option<Box<SeriesDetails>>,
+        episodes: Vec<Episode>,
+    },
+    Sources(Vec<Source>),
+    EpisodeLink {
+        provider: String,
+        resolved: ResolvedEpisode,
+    },
+    /// Master playlist to choose the quality of
+    Qualities {
+        provider: String,
+        resolved: ResolvedEpisode,
+        variants: Vec<Variant>,
+    },
+}
+
+enum Message {
+    Progress(String),
+    Done(Result<Response>),
+}
+
+/// Handle given to the tasks to report what they are doing
+pub struct Progress {
+    id: u64,
+    sender: Sender<(u64, Message)>,
+}
+
+impl Progress {
+    pub fn report(&self, description: &str) {
+        let _ = self
+            .sender
+            .send((self.id, Message::Progress(description.to_owned())));
+    }
+}
+
+struct Job {
+    id: u64,
+    description: String,
+    started: Instant,
+}
+
+/// Runs the provider calls outside of the ui thread. Only one job is tracked at a time, starting
+/// a new one or cancelling discards the result of the previous one.
+pub struct Worker {
+    sender: Sender<(u64, Message)>,
+    receiver: Receiver<(u64, Message)>,
+    current: Option<Job>,
+    next_id: u64,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            current: None,
+            next_id: 0,
+        }
+    }
+}
+
+impl Worker {
+    pub fn spawn<F>(&mut self, description: &str, task: F)
+    where
+        F: FnOnce(&Progress) -> Result<Response> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.current = Some(Job {
+            id,
+            description: description.to_owned(),
+            started: Instant::now(),
+        });
+
+        let progress = Progress {
+            id,
+            sender: self.sender.clone(),
+        };
+        thread::spawn(move || {
+            let result = task(&progress);
+            let _ = progress.sender.send((id, Message::Done(result)));
+        });
+    }
+
+    /// The blocking request can not be interrupted, its result is ignored when it arrives
+    pub fn cancel(&mut self) {
+        self.current = None;
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Returns the result of the current job once it finishes
+    pub fn poll(&mut self) -> Option<Result<Response>> {
+        while let Ok((id, message)) = self.receiver.try_recv() {
+            let Some(job) = self.current.as_mut().filter(|job| job.id == id) else {
+                continue;
+            };
+
+            match message {
+                Message::Progress(description) => job.description = description,
+                Message::Done(result) => {
+                    self.current = None;
+                    return Some(result);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn status(&self) -> Option<String> {
+        self.current.as_ref().map(|job| {
+            let frame = (job.started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+            format!("{} {} <Esc para cancelar>", SPINNER[frame], job.description)
+        })
+    }
+}